@@ -0,0 +1,331 @@
+//! Errors of batch operations.
+//!
+//! A batch endpoint may fail for some of the items
+//! only. [Batch] collects an error per failed item
+//! into a single [Terror], where they are reported
+//! under `errors`, each keyed by the item index or ID:
+//!
+//! ```json
+//! {
+//!   "status": 207,
+//!   "message": "some users were not created",
+//!   "errors": [
+//!     { "item": 0, "error": { "status": 409, "message": "user exists" } },
+//!     { "item": "bob", "error": { "status": 422, "message": "invalid email" } }
+//!   ]
+//! }
+//! ```
+//!
+//! A batch without item errors did not fail, so it
+//! concludes into no [Terror] at all.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::batch::{Batch, StatusPolicy};
+//! use terror::Terror;
+//!
+//! let built = Batch::new("some users were not created")
+//!     .add(0, Terror::new(409, "user exists").build())
+//!     .add("bob", Terror::new(422, "invalid email").build())
+//!     .status_policy(StatusPolicy::Common(400))
+//!     .build()
+//!     .expect("items failed");
+//! assert_eq!(400, built.status);
+//! assert_eq!(2, built.errors.len());
+//!
+//! assert!(Batch::new("nothing failed").build().is_none());
+//! ```
+
+use serde_derive::{Serialize, Deserialize};
+
+use crate::{Builder, Terror};
+
+/// Status of `207 Multi-Status`.
+const MULTI_STATUS: u16 = 207;
+
+/// Key of an item of a batch.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Hash)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum ItemKey {
+
+    /// Position of the item in the batch
+    Index(usize),
+
+    /// ID of the item
+    Id(String)
+
+}
+
+impl From<usize> for ItemKey {
+    fn from(index: usize) -> Self {
+        ItemKey::Index(index)
+    }
+}
+
+impl From<String> for ItemKey {
+    fn from(id: String) -> Self {
+        ItemKey::Id(id)
+    }
+}
+
+impl From<&str> for ItemKey {
+    fn from(id: &str) -> Self {
+        ItemKey::Id(String::from(id))
+    }
+}
+
+/// An error of a single item of a batch.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ItemError {
+
+    /// Key of the failed item
+    pub item: ItemKey,
+
+    /// The error of the item
+    #[cfg_attr(feature = "utoipa", schema(no_recursion))]
+    pub error: Terror
+
+}
+
+impl ItemError {
+
+    /// Constructs an error of the provided item.
+    pub fn new<K: Into<ItemKey>>(item: K, error: Terror) -> ItemError {
+        ItemError {
+            item: item.into(),
+            error
+        }
+    }
+
+}
+
+/// Policy of the overall status of a [Batch].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StatusPolicy {
+
+    /// The status all item errors share or,
+    /// if they differ, the provided one,
+    /// e.g. `207` or `400`
+    Common(u16),
+
+    /// Always the provided status
+    Fixed(u16)
+
+}
+
+impl Default for StatusPolicy {
+
+    /// The common status or `207 Multi-Status`.
+    fn default() -> Self {
+        StatusPolicy::Common(MULTI_STATUS)
+    }
+
+}
+
+impl StatusPolicy {
+
+    /// Decides the overall status of the item errors.
+    pub fn status_for(&self, errors: &[ItemError]) -> u16 {
+        match *self {
+            StatusPolicy::Fixed(status) => status,
+            StatusPolicy::Common(fallback) => {
+                let mut statuses = errors.iter()
+                    .map(|item| item.error.status);
+                match statuses.next() {
+                    Some(first) if statuses.all(|status| status == first) => first,
+                    _ => fallback
+                }
+            }
+        }
+    }
+
+}
+
+/// A builder of [Terror] aggregating errors of
+/// the items of a batch operation.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    message: String,
+    errors: Vec<ItemError>,
+    policy: StatusPolicy
+}
+
+impl Batch {
+
+    /// Constructs an empty batch with the
+    /// provided overall message.
+    pub fn new<K: Into<String>>(message: K) -> Batch {
+        Batch {
+            message: message.into(),
+            errors: Vec::new(),
+            policy: StatusPolicy::default()
+        }
+    }
+
+    /// Adds an error of the provided item.
+    pub fn add<K: Into<ItemKey>>(mut self, item: K, error: Terror) -> Batch {
+        self.errors.push(ItemError::new(item, error));
+        self
+    }
+
+    /// Sets the policy of the overall status.
+    pub fn status_policy(mut self, policy: StatusPolicy) -> Batch {
+        self.policy = policy;
+        self
+    }
+
+    /// Tells whether no item errors were added;
+    /// handy to tell a fully successful batch.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Reports the number of item errors.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Concludes the batch into a [Builder], e.g.
+    /// to add an error code or details on top;
+    /// `None` if no item errors were added.
+    pub fn into_builder(self) -> Option<Builder> {
+        if self.errors.is_empty() {
+            return None;
+        }
+        let status = self.policy.status_for(&self.errors);
+        let builder = Terror::new(status, self.message)
+            .add_item_errors(self.errors);
+        Some(builder)
+    }
+
+    /// Concludes the batch into a [Terror];
+    /// `None` if no item errors were added.
+    pub fn build(self) -> Option<Terror> {
+        self.into_builder()
+            .map(Builder::build)
+    }
+
+}
+
+impl<K: Into<ItemKey>> Extend<(K, Terror)> for Batch {
+    fn extend<I: IntoIterator<Item = (K, Terror)>>(&mut self, iter: I) {
+        self.errors.extend(iter.into_iter()
+            .map(|(item, error)| ItemError::new(item, error)));
+    }
+}
+
+#[cfg(test)]
+mod batch_test {
+    use serde_json::json;
+    use crate::batch::{Batch, ItemError, ItemKey, StatusPolicy};
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn common_status() {
+        let built = Batch::new("some users were not created")
+            .add(0, Terror::new(409, "user exists").build())
+            .add(3, Terror::new(409, "user exists").build())
+            .build()
+            .unwrap();
+
+        assert_eq!(409, built.status);
+        assert_eq!(ItemKey::Index(3), built.errors[1].item);
+    }
+
+    #[test]
+    fn fallback_status() {
+        let mixed = Batch::new("some users were not created")
+            .add(0, Terror::new(409, "user exists").build())
+            .add(1, Terror::new(422, "invalid email").build());
+
+        assert_eq!(207, mixed.clone().build().unwrap().status);
+        assert_eq!(
+            400,
+            mixed.status_policy(StatusPolicy::Common(400)).build().unwrap().status
+        );
+    }
+
+    #[test]
+    fn empty_batch() {
+        let empty = Batch::new("nothing failed");
+
+        assert!(empty.clone().into_builder().is_none());
+        assert!(empty.clone().build().is_none());
+        assert!(empty.status_policy(StatusPolicy::Fixed(400)).build().is_none());
+    }
+
+    #[test]
+    fn fixed_status() {
+        let built = Batch::new("some users were not created")
+            .add(0, Terror::new(409, "user exists").build())
+            .status_policy(StatusPolicy::Fixed(400))
+            .build()
+            .unwrap();
+
+        assert_eq!(400, built.status);
+    }
+
+    #[test]
+    fn extend_batch() {
+        let mut batch = Batch::new("some users were not created");
+        assert!(batch.is_empty());
+
+        batch.extend([
+            ("alice", Terror::new(409, "user exists").build()),
+            ("bob", Terror::new(409, "user exists").build())
+        ]);
+
+        assert_eq!(2, batch.len());
+        assert_eq!(ItemKey::from("bob"), batch.build().unwrap().errors[1].item);
+    }
+
+    #[test]
+    fn serialize_batch() -> R {
+        let built = Batch::new("some users were not created")
+            .add(0, Terror::new(409, "user exists").build())
+            .add("bob", Terror::new(422, "invalid email").build())
+            .into_builder()
+            .unwrap()
+            .error_code("users.batch")
+            .build();
+
+        let actual = serde_json::to_value(&built)?;
+        assert_eq!(json!(207), actual["status"]);
+        assert_eq!(json!("users.batch"), actual["error_code"]);
+        assert_eq!(json!(0), actual["errors"][0]["item"]);
+        assert_eq!(json!(409), actual["errors"][0]["error"]["status"]);
+        assert_eq!(json!("bob"), actual["errors"][1]["item"]);
+        assert_eq!(json!("invalid email"), actual["errors"][1]["error"]["message"]);
+
+        let as_struct: Terror = serde_json::from_value(actual)?;
+        assert_eq!(built, as_struct);
+        Ok(())
+    }
+
+    #[test]
+    fn skip_empty_errors() -> R {
+        let built = Terror::new(500, "generic error")
+            .build();
+
+        let actual = serde_json::to_value(&built)?;
+        assert!(actual.get("errors").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn add_item_error_to_builder() {
+        let built = Terror::new(400, "some users were not created")
+            .add_item_error(ItemError::new(1, Terror::new(422, "invalid email").build()))
+            .build();
+
+        assert_eq!(1, built.errors.len());
+    }
+
+}
@@ -0,0 +1,333 @@
+//! Catalogs of error codes.
+//!
+//! [Terror::error_code](crate::Terror::error_code) is free-form, so nothing keeps
+//! services from misspelling a code or reusing one for
+//! different errors. Instead, each error may be declared
+//! once as an [ErrorDef] constant, with its status, short
+//! message, message and documentation URL, and reported
+//! via [Terror::from_def](crate::Terror::from_def).
+//!
+//! A [Catalog] collects the declared errors and rejects
+//! duplicate codes, which is best checked at startup or
+//! in a test.
+//!
+//! Note that a catalog only knows the errors passed to
+//! it: nothing collects [ErrorDef]s on its own, so a
+//! duplicate declared in a module, whose errors are never
+//! registered, goes unnoticed. [Catalog::check] reports
+//! every duplicate among all the errors of a service at
+//! once, e.g. the `ALL` constants generated for each
+//! catalog file, see feature `codegen`, along with
+//! hand-written ones.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::catalog::{Catalog, ErrorDef};
+//! use terror::Terror;
+//!
+//! const USER_MISSING: ErrorDef = ErrorDef::new("user.missing", 404, "no such user")
+//!     .shorthand("Not found")
+//!     .doc_url("https://docs.example.com/errors/user.missing");
+//! const USER_EXISTS: ErrorDef = ErrorDef::new("user.exists", 409, "user exists");
+//!
+//! let catalog = Catalog::from_defs([&USER_MISSING, &USER_EXISTS]).unwrap();
+//! assert_eq!(Some(&USER_EXISTS), catalog.get("user.exists"));
+//!
+//! let built = Terror::from_def(&USER_MISSING)
+//!     .add_text_detail("user", "42")
+//!     .build();
+//! assert_eq!(404, built.status);
+//! assert_eq!(Some(String::from("user.missing")), built.error_code);
+//! ```
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Name of the detail carrying [ErrorDef::doc_url].
+pub const DOC_URL_DETAIL: &str = "doc_url";
+
+/// A declaration of an error, to be reported
+/// via [Terror::from_def](crate::Terror::from_def).
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ErrorDef {
+
+    /// Error code, unique within a [Catalog]
+    pub code: &'static str,
+
+    /// HTTP status code
+    pub status: u16,
+
+    /// Shortened error message; nullable
+    pub shorthand: Option<&'static str>,
+
+    /// Full error message, which may contain
+    /// placeholders, [interpolated](crate::Builder::interpolate)
+    /// from the details when the error is built;
+    /// `{{` and `}}` stand for literal braces
+    pub message: &'static str,
+
+    /// URL of the documentation of the
+    /// error, reported as a detail named
+    /// [DOC_URL_DETAIL]; nullable
+    pub doc_url: Option<&'static str>
+
+}
+
+impl ErrorDef {
+
+    /// Declares an error with the minimal data.
+    pub const fn new(code: &'static str, status: u16, message: &'static str) -> ErrorDef {
+        ErrorDef {
+            code,
+            status,
+            shorthand: None,
+            message,
+            doc_url: None
+        }
+    }
+
+    /// Adds a shortened error message.
+    pub const fn shorthand(mut self, shorthand: &'static str) -> ErrorDef {
+        self.shorthand = Some(shorthand);
+        self
+    }
+
+    /// Adds a URL of the documentation of the error.
+    pub const fn doc_url(mut self, url: &'static str) -> ErrorDef {
+        self.doc_url = Some(url);
+        self
+    }
+
+}
+
+/// Registration of an error code, which is
+/// already taken within a [Catalog].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DuplicateCode {
+
+    /// The error already registered
+    pub registered: &'static ErrorDef,
+
+    /// The error rejected
+    pub rejected: &'static ErrorDef
+
+}
+
+impl Display for DuplicateCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate error code '{}'", self.rejected.code)
+    }
+}
+
+impl Error for DuplicateCode {}
+
+/// A registry of [ErrorDef]s by code.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Catalog {
+    defs: BTreeMap<&'static str, &'static ErrorDef>
+}
+
+impl Catalog {
+
+    /// Constructs an empty catalog.
+    pub fn new() -> Catalog {
+        Catalog::default()
+    }
+
+    /// Constructs a catalog of the provided errors,
+    /// failing on the first duplicate code.
+    pub fn from_defs<I>(defs: I) -> Result<Catalog, DuplicateCode>
+        where I: IntoIterator<Item = &'static ErrorDef>
+    {
+        let mut catalog = Catalog::new();
+        for def in defs {
+            catalog.register(def)?;
+        }
+        Ok(catalog)
+    }
+
+    /// Constructs a catalog of the provided errors,
+    /// reporting every duplicate code rather than the
+    /// first; meant for a test, which passes every
+    /// [ErrorDef] the service declares.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use terror::catalog::{Catalog, ErrorDef};
+    ///
+    /// mod users {
+    ///     use terror::catalog::ErrorDef;
+    ///     pub const MISSING: ErrorDef = ErrorDef::new("user.missing", 404, "no such user");
+    ///     pub const ALL: [&ErrorDef; 1] = [&MISSING];
+    /// }
+    /// const ORDER_MISSING: ErrorDef = ErrorDef::new("user.missing", 404, "no such order");
+    ///
+    /// let errs = Catalog::check(users::ALL.into_iter().chain([&ORDER_MISSING])).unwrap_err();
+    /// assert_eq!(1, errs.len());
+    /// assert_eq!(&ORDER_MISSING, errs[0].rejected);
+    /// ```
+    pub fn check<I>(defs: I) -> Result<Catalog, Vec<DuplicateCode>>
+        where I: IntoIterator<Item = &'static ErrorDef>
+    {
+        let mut catalog = Catalog::new();
+        let duplicates: Vec<DuplicateCode> = defs.into_iter()
+            .filter_map(|def| catalog.register(def).err())
+            .collect();
+        if duplicates.is_empty() {
+            Ok(catalog)
+        } else {
+            Err(duplicates)
+        }
+    }
+
+    /// Registers the error unless its code is taken;
+    /// registering the same code twice is an error even
+    /// if both declarations are equal.
+    pub fn register(&mut self, def: &'static ErrorDef) -> Result<(), DuplicateCode> {
+        match self.defs.get(def.code) {
+            Some(registered) => Err(DuplicateCode {
+                registered,
+                rejected: def
+            }),
+            None => {
+                self.defs.insert(def.code, def);
+                Ok(())
+            }
+        }
+    }
+
+    /// Looks the error up by code.
+    pub fn get(&self, code: &str) -> Option<&'static ErrorDef> {
+        self.defs.get(code).copied()
+    }
+
+    /// Tells whether the code is registered.
+    pub fn contains(&self, code: &str) -> bool {
+        self.defs.contains_key(code)
+    }
+
+    /// Iterates over the errors in order of codes.
+    pub fn iter(&self) -> impl Iterator<Item = &'static ErrorDef> + '_ {
+        self.defs.values().copied()
+    }
+
+    /// Reports the number of registered errors.
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    /// Tells whether no errors are registered.
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+
+}
+
+#[cfg(test)]
+mod catalog_test {
+    use serde_json::json;
+    use crate::catalog::{Catalog, ErrorDef, DOC_URL_DETAIL};
+    use crate::{MissingKey, Terror};
+
+    const USER_MISSING: ErrorDef = ErrorDef::new("user.missing", 404, "no such user")
+        .shorthand("Not found")
+        .doc_url("https://docs.example.com/errors/user.missing");
+    const USER_EXISTS: ErrorDef = ErrorDef::new("user.exists", 409, "user exists");
+    const USER_TAKEN: ErrorDef = ErrorDef::new("user.exists", 422, "user name taken");
+
+    #[test]
+    fn build_from_def() {
+        let built = Terror::from_def(&USER_MISSING)
+            .add_text_detail("user", "42")
+            .build();
+
+        assert_eq!(404, built.status);
+        assert_eq!("no such user", built.message);
+        assert_eq!(Some(String::from("Not found")), built.short_message);
+        assert_eq!(Some(String::from("user.missing")), built.error_code);
+        assert_eq!(
+            Some(&json!("https://docs.example.com/errors/user.missing")),
+            built.details.get(DOC_URL_DETAIL)
+        );
+        assert_eq!(Some(&json!("42")), built.details.get("user"));
+    }
+
+    #[test]
+    fn build_from_def_w_placeholders() {
+        const USER_NAMED: ErrorDef = ErrorDef::new("user.named", 409, "user {name} exists")
+            .shorthand("{name} taken");
+
+        let built = Terror::from_def(&USER_NAMED)
+            .add_text_detail("name", "alice")
+            .build();
+        assert_eq!("user alice exists", built.message);
+        assert_eq!(Some(String::from("alice taken")), built.short_message);
+
+        let failed = Terror::from_def(&USER_NAMED)
+            .interpolate(MissingKey::Error)
+            .try_build();
+        assert_eq!("name", failed.unwrap_err().key());
+    }
+
+    #[test]
+    fn build_from_minimal_def() {
+        let built = Terror::from_def(&USER_EXISTS).build();
+
+        assert_eq!(409, built.status);
+        assert_eq!(Some(String::from("user.exists")), built.error_code);
+        assert!(built.details.is_empty());
+    }
+
+    #[test]
+    fn look_up_defs() {
+        let catalog = Catalog::from_defs([&USER_MISSING, &USER_EXISTS]).unwrap();
+
+        assert_eq!(2, catalog.len());
+        assert!(catalog.contains("user.missing"));
+        assert_eq!(Some(&USER_EXISTS), catalog.get("user.exists"));
+        assert_eq!(None, catalog.get("user.invalid"));
+        assert_eq!(
+            vec!["user.exists", "user.missing"],
+            catalog.iter().map(|def| def.code).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reject_duplicate_code() {
+        let err = Catalog::from_defs([&USER_MISSING, &USER_EXISTS, &USER_TAKEN]).unwrap_err();
+
+        assert_eq!(&USER_EXISTS, err.registered);
+        assert_eq!(&USER_TAKEN, err.rejected);
+        assert_eq!("duplicate error code 'user.exists'", err.to_string());
+    }
+
+    #[test]
+    fn report_every_duplicate_code() {
+        const USER_GONE: ErrorDef = ErrorDef::new("user.missing", 410, "user deleted");
+
+        let errs = Catalog::check([&USER_MISSING, &USER_EXISTS, &USER_TAKEN, &USER_GONE])
+            .unwrap_err();
+
+        assert_eq!(2, errs.len());
+        assert_eq!(&USER_TAKEN, errs[0].rejected);
+        assert_eq!(&USER_MISSING, errs[1].registered);
+        assert_eq!(&USER_GONE, errs[1].rejected);
+
+        let catalog = Catalog::check([&USER_MISSING, &USER_EXISTS]).unwrap();
+        assert_eq!(2, catalog.len());
+    }
+
+    #[test]
+    fn reject_same_def_twice() {
+        let mut catalog = Catalog::new();
+
+        assert!(catalog.register(&USER_MISSING).is_ok());
+        assert!(catalog.register(&USER_MISSING).is_err());
+        assert_eq!(1, catalog.len());
+    }
+
+}
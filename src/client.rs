@@ -0,0 +1,193 @@
+//! Client side of [Terror] for feature `reqwest`.
+//!
+//! [TerrorResponse] extends [reqwest::Response] with
+//! a way to tell successful responses from errors,
+//! reported by another service as [Terror] bodies.
+//!
+//! Unlike the rest of the crate, requires Rust 1.75,
+//! as [TerrorResponse] returns `impl Future`.
+//!
+//! ### Examples
+//!
+//! ```rust,no_run
+//! use terror::client::TerrorResponse;
+//! use terror::Terror;
+//!
+//! async fn fetch_user(id: u64) -> Result<String, Terror> {
+//!     let response = reqwest::get(format!("http://users/{}", id)).await
+//!         .map_err(|err| Terror::from_source(err).build())?
+//!         .error_for_terror().await?;
+//!     response.text().await
+//!         .map_err(|err| Terror::from_source(err).build())
+//! }
+//! ```
+
+use std::future::Future;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Response;
+
+use crate::{Problem, Terror, PROBLEM_CONTENT_TYPE};
+
+/// Message of errors, which came
+/// with neither a status reason nor a body.
+const UPSTREAM_MESSAGE: &str = "upstream error";
+
+/// Extension of [Response] with [Terror] parsing.
+pub trait TerrorResponse: Sized {
+
+    /// Passes the response through if its status
+    /// is successful; otherwise, reports the body as
+    /// [Terror].
+    ///
+    /// The body is parsed as [Terror] or, if sent as
+    /// `application/problem+json`, as [Problem]. If it
+    /// is neither, the error is built from the response
+    /// status and the raw body text as message; should
+    /// the body be empty, the canonical reason of the
+    /// status is used instead.
+    fn error_for_terror(self) -> impl Future<Output = Result<Self, Terror>> + Send;
+
+}
+
+impl TerrorResponse for Response {
+
+    async fn error_for_terror(self) -> Result<Self, Terror> {
+        let status = self.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(self);
+        }
+
+        let is_problem = self.headers().get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(PROBLEM_CONTENT_TYPE));
+        let body = match self.text().await {
+            Ok(body) => body,
+            Err(err) => return Err(Terror::new(status.as_u16(), err.to_string())
+                .source(err)
+                .build())
+        };
+
+        let parsed = match is_problem {
+            true => serde_json::from_str::<Problem>(&body).map(Terror::from),
+            false => serde_json::from_str::<Terror>(&body)
+        };
+        match parsed {
+            Ok(terror) => Err(terror),
+            Err(_) => {
+                let message = match body.trim() {
+                    "" => status.canonical_reason().unwrap_or(UPSTREAM_MESSAGE),
+                    text => text
+                };
+                Err(Terror::new(status.as_u16(), message).build())
+            }
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod client_test {
+    use serde_json::json;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use crate::client::TerrorResponse;
+    use crate::{Problem, Terror, PROBLEM_CONTENT_TYPE};
+
+    type R = anyhow::Result<()>;
+
+    #[tokio::test]
+    async fn pass_success_through() -> R {
+        let server = serve("/users/1", ResponseTemplate::new(200).set_body_string("alice")).await;
+
+        let response = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await;
+
+        assert_eq!("alice", response.unwrap().text().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_terror() -> R {
+        let sent = Terror::new(404, "no such user")
+            .error_code("user.missing")
+            .add_text_detail("user", "1")
+            .build();
+        let server = serve("/users/1", ResponseTemplate::new(404).set_body_json(&sent)).await;
+
+        let err = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await
+            .unwrap_err();
+
+        assert_eq!(sent, err);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parse_problem() -> R {
+        let sent: Problem = Terror::new(409, "version conflict")
+            .error_code("entity.conflict")
+            .build()
+            .into();
+        let template = ResponseTemplate::new(409)
+            .set_body_raw(serde_json::to_vec(&sent)?, PROBLEM_CONTENT_TYPE);
+        let server = serve("/users/1", template).await;
+
+        let err = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await
+            .unwrap_err();
+
+        assert_eq!(409, err.status);
+        assert_eq!("version conflict", err.message);
+        assert_eq!(Some(String::from("entity.conflict")), err.error_code);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_body_text() -> R {
+        let server = serve("/users/1", ResponseTemplate::new(502).set_body_string("bad gateway\n")).await;
+
+        let err = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await
+            .unwrap_err();
+
+        assert_eq!(502, err.status);
+        assert_eq!("bad gateway", err.message);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fall_back_to_status_reason() -> R {
+        let server = serve("/users/1", ResponseTemplate::new(503)).await;
+
+        let err = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await
+            .unwrap_err();
+
+        assert_eq!(503, err.status);
+        assert_eq!("Service Unavailable", err.message);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn fall_back_on_foreign_json() -> R {
+        let server = serve("/users/1", ResponseTemplate::new(400).set_body_json(json!({ "error": "oops" }))).await;
+
+        let err = reqwest::get(format!("{}/users/1", server.uri())).await?
+            .error_for_terror().await
+            .unwrap_err();
+
+        assert_eq!(400, err.status);
+        assert_eq!(r#"{"error":"oops"}"#, err.message);
+        Ok(())
+    }
+
+    async fn serve(route: &str, response: ResponseTemplate) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(path(route))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        server
+    }
+
+}
@@ -0,0 +1,186 @@
+//! Error timestamps for features `time` and `time_crate`.
+//!
+//! By default, [Terror](crate::Terror) is stamped
+//! with the present [System] time. Another [Clock]
+//! may be installed process-wide via [set_clock], or
+//! used for a single error via
+//! [Builder::clock](crate::Builder::clock), e.g. to
+//! make serialised errors deterministic in tests.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::clock::{Clock, Frozen, System};
+//! use terror::Terror;
+//!
+//! let at = System.now();
+//! terror::clock::set_clock(Frozen(at));
+//!
+//! let built = Terror::new(404, "no such user")
+//!     .build();
+//! assert_eq!(at, built.timestamp);
+//! ```
+
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use crate::global::Global;
+use crate::timestamp::{self, Timestamp};
+
+/// A source of error timestamps.
+pub trait Clock: Send + Sync {
+
+    /// Reports the present moment.
+    fn now(&self) -> Timestamp;
+
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Timestamp {
+        (**self).now()
+    }
+}
+
+/// Reports the system time; the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct System;
+
+impl Clock for System {
+    fn now(&self) -> Timestamp {
+        timestamp::now()
+    }
+}
+
+/// Always reports the same moment.
+#[derive(Debug, Clone, Copy)]
+pub struct Frozen(pub Timestamp);
+
+impl Clock for Frozen {
+    fn now(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Reports a moment, which may be moved
+/// explicitly; intended for tests.
+///
+/// Wrap into an [Arc] to keep control over
+/// the clock once it is [installed](set_clock).
+#[derive(Debug)]
+pub struct Mock(Mutex<Timestamp>);
+
+impl Mock {
+
+    /// Constructs a clock, which reports
+    /// the provided moment.
+    pub fn new(at: Timestamp) -> Mock {
+        Mock(Mutex::new(at))
+    }
+
+    /// Moves the clock to the provided moment.
+    pub fn set(&self, at: Timestamp) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = at;
+    }
+
+    /// Moves the clock forward by the provided duration.
+    pub fn advance(&self, by: Duration) {
+        let mut at = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        *at = timestamp::add(*at, by);
+    }
+
+}
+
+impl Clock for Mock {
+    fn now(&self) -> Timestamp {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The clock errors are stamped by, unless a
+/// builder is given its own; [System] if not set.
+static CLOCK: Global<dyn Clock> = Global::new();
+
+/// Makes every error built from then on, in any
+/// thread, stamped by the provided clock, unless
+/// [Builder::clock](crate::Builder::clock) says
+/// otherwise; e.g. installing a [Frozen] clock
+/// makes the timestamps of serialised errors
+/// deterministic in tests.
+pub fn set_clock<C: Clock + 'static>(clock: C) {
+    CLOCK.set(Arc::new(clock));
+}
+
+/// Goes back to stamping errors with the
+/// [System] time.
+pub fn reset_clock() {
+    CLOCK.reset();
+}
+
+/// Reads the time off the installed clock,
+/// or the system time if none is installed.
+pub(crate) fn now() -> Timestamp {
+    match CLOCK.get() {
+        Some(clock) => clock.now(),
+        None => System.now()
+    }
+}
+
+#[cfg(test)]
+mod clock_test {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use serde_json::json;
+    use crate::clock::{Clock, Frozen, Mock, System};
+    use crate::timestamp::{self, Timestamp};
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn system_clock() {
+        let before = timestamp::now();
+        let now = System.now();
+
+        assert!(before <= now);
+        assert!(now <= timestamp::now());
+    }
+
+    #[test]
+    fn frozen_clock() -> R {
+        let at = at("2022-01-01T21:00:00Z")?;
+        let clock = Frozen(at);
+
+        assert_eq!(at, clock.now());
+        assert_eq!(at, clock.now());
+        Ok(())
+    }
+
+    #[test]
+    fn mock_clock() -> R {
+        let clock = Arc::new(Mock::new(at("2022-01-01T21:00:00Z")?));
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(at("2022-01-01T21:01:30Z")?, clock.now());
+
+        clock.set(at("2023-06-01T00:00:00Z")?);
+        assert_eq!(at("2023-06-01T00:00:00Z")?, Clock::now(&clock));
+        Ok(())
+    }
+
+    #[test]
+    fn build_w_clock() -> R {
+        let clock = Frozen(at("2022-01-01T21:00:00Z")?);
+
+        let built = Terror::new(404, "generic error")
+            .clock(&clock)
+            .build();
+
+        let actual = serde_json::to_value(built)?;
+        assert_eq!(json!("2022-01-01T21:00:00Z"), actual["timestamp"]);
+        Ok(())
+    }
+
+    fn at(ts: &str) -> anyhow::Result<Timestamp> {
+        timestamp::parse_rfc3339(ts).map_err(anyhow::Error::msg)
+    }
+
+}
@@ -0,0 +1,488 @@
+//! Code generation of error catalogs.
+//!
+//! Error definitions may be maintained in a YAML or
+//! TOML file rather than in code; this module, meant to
+//! be called from a build script, turns such a file into
+//! a [catalog](crate::catalog) and into a Markdown
+//! reference page.
+//!
+//! The file lists errors under `errors`, each with
+//! a `code`, a `status`, a `message` and, optionally,
+//! a `shorthand`, a `doc_url` and a `description`,
+//! which only goes into the reference page:
+//!
+//! ```yaml
+//! errors:
+//!   - code: user.missing
+//!     status: 404
+//!     shorthand: Not found
+//!     message: no such user
+//!     description: The user was deleted or never existed.
+//! ```
+//!
+//! For each error, the generated Rust code declares an
+//! [ErrorDef](crate::catalog::ErrorDef) constant named
+//! after the code, e.g. `USER_MISSING`, and a variant of
+//! an enum, e.g. `UserMissing`, whose `builder` returns
+//! a [Builder](crate::Builder) preconfigured with the
+//! status, the error code and the short message. All the
+//! constants are also listed in `ALL`, so that a
+//! [Catalog](crate::catalog::Catalog) can be built.
+//!
+//! ### Examples
+//!
+//! In `build.rs`:
+//! ```rust,no_run
+//! use std::path::PathBuf;
+//! use terror::codegen::Definitions;
+//!
+//! let defs = Definitions::load("errors.yaml").unwrap();
+//! let out = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+//! std::fs::write(out.join("errors.rs"), defs.to_rust("ApiError")).unwrap();
+//! std::fs::write("ERRORS.md", defs.to_markdown("Errors")).unwrap();
+//! println!("cargo:rerun-if-changed=errors.yaml");
+//! ```
+//!
+//! And then, in the crate:
+//! ```rust,ignore
+//! include!(concat!(env!("OUT_DIR"), "/errors.rs"));
+//!
+//! let built = ApiError::UserMissing.builder()
+//!     .add_text_detail("user", "42")
+//!     .build();
+//! ```
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+use serde_derive::Deserialize;
+
+/// Definition of a single error.
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+pub struct Definition {
+
+    /// Error code
+    pub code: String,
+
+    /// HTTP status code
+    pub status: u16,
+
+    /// Shortened error message; nullable
+    #[serde(default)]
+    pub shorthand: Option<String>,
+
+    /// Full error message
+    pub message: String,
+
+    /// URL of the documentation of the error; nullable
+    #[serde(default)]
+    pub doc_url: Option<String>,
+
+    /// Description for the reference page; nullable
+    #[serde(default)]
+    pub description: Option<String>
+
+}
+
+/// Failure to load error definitions.
+#[derive(Debug)]
+pub enum CodegenError {
+
+    /// The file could not be read
+    Io {
+        path: PathBuf,
+        cause: io::Error
+    },
+
+    /// The file extension is neither
+    /// `yaml`, `yml` nor `toml`
+    UnknownFormat {
+        path: PathBuf
+    },
+
+    /// The YAML input is malformed
+    Yaml(serde_norway::Error),
+
+    /// The TOML input is malformed
+    Toml(toml::de::Error),
+
+    /// The code does not make a Rust identifier,
+    /// or makes one taken by the generated code
+    InvalidCode {
+        code: String
+    },
+
+    /// The status is not an HTTP status code,
+    /// i.e. outside of `100..=599`
+    InvalidStatus {
+        code: String,
+        status: u16
+    },
+
+    /// The code, or the Rust identifier made of
+    /// it, is taken by another error
+    DuplicateCode {
+        code: String
+    }
+
+}
+
+impl Display for CodegenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::Io { path, cause } => write!(f, "failed to read '{}': {}", path.display(), cause),
+            CodegenError::UnknownFormat { path } => write!(f, "unknown format of '{}'", path.display()),
+            CodegenError::Yaml(cause) => write!(f, "malformed YAML: {}", cause),
+            CodegenError::Toml(cause) => write!(f, "malformed TOML: {}", cause),
+            CodegenError::InvalidCode { code } => write!(f, "error code '{}' does not make an identifier", code),
+            CodegenError::InvalidStatus { code, status } => write!(f, "status {} of error code '{}' is not an HTTP status", status, code),
+            CodegenError::DuplicateCode { code } => write!(f, "duplicate error code '{}'", code)
+        }
+    }
+}
+
+impl Error for CodegenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodegenError::Io { cause, .. } => Some(cause),
+            CodegenError::Yaml(cause) => Some(cause),
+            CodegenError::Toml(cause) => Some(cause),
+            _ => None
+        }
+    }
+}
+
+/// Layout of definition files.
+#[derive(Deserialize)]
+struct File {
+    #[serde(default)]
+    errors: Vec<Definition>
+}
+
+/// Validated definitions of errors,
+/// in the order of the input.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Definitions {
+    errors: Vec<Definition>
+}
+
+impl Definitions {
+
+    /// Loads the definitions from a file, in YAML or
+    /// TOML as told by the extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Definitions, CodegenError> {
+        let path = path.as_ref();
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        let parse = match extension.as_deref() {
+            Some("yaml" | "yml") => Definitions::from_yaml,
+            Some("toml") => Definitions::from_toml,
+            _ => return Err(CodegenError::UnknownFormat { path: path.to_path_buf() })
+        };
+        let input = std::fs::read_to_string(path)
+            .map_err(|cause| CodegenError::Io { path: path.to_path_buf(), cause })?;
+        parse(&input)
+    }
+
+    /// Parses the definitions from YAML.
+    pub fn from_yaml(input: &str) -> Result<Definitions, CodegenError> {
+        let file: File = serde_norway::from_str(input)
+            .map_err(CodegenError::Yaml)?;
+        Definitions::new(file.errors)
+    }
+
+    /// Parses the definitions from TOML,
+    /// where errors are an array of tables.
+    pub fn from_toml(input: &str) -> Result<Definitions, CodegenError> {
+        let file: File = toml::from_str(input)
+            .map_err(CodegenError::Toml)?;
+        Definitions::new(file.errors)
+    }
+
+    /// Validates the definitions.
+    pub fn new(errors: Vec<Definition>) -> Result<Definitions, CodegenError> {
+        let mut taken = HashSet::new();
+        for error in &errors {
+            let words = words_of(&error.code);
+            let invalid = words.is_empty()
+                || words[0].starts_with(|c: char| c.is_ascii_digit())
+                || RESERVED.iter().any(|reserved| words == [*reserved]);
+            if invalid {
+                return Err(CodegenError::InvalidCode { code: error.code.clone() });
+            }
+            if !(100..=599).contains(&error.status) {
+                return Err(CodegenError::InvalidStatus {
+                    code: error.code.clone(),
+                    status: error.status
+                });
+            }
+            if !taken.insert(words) {
+                return Err(CodegenError::DuplicateCode { code: error.code.clone() });
+            }
+        }
+        Ok(Definitions { errors })
+    }
+
+    /// Lists the definitions.
+    pub fn errors(&self) -> &[Definition] {
+        &self.errors
+    }
+
+    /// Generates Rust code declaring the constants,
+    /// `ALL` and the enum of the provided name.
+    pub fn to_rust(&self, enum_name: &str) -> String {
+        let mut out = String::new();
+        out.push_str("// Generated by terror::codegen; do not edit.\n");
+
+        for error in &self.errors {
+            let _ = write!(
+                out,
+                "\n/// {}\npub const {}: ::terror::catalog::ErrorDef = ::terror::catalog::ErrorDef::new({:?}, {}, {:?})",
+                doc_line(&error.message), const_name(&error.code), error.code, error.status, error.message
+            );
+            if let Some(shorthand) = &error.shorthand {
+                let _ = write!(out, "\n    .shorthand({:?})", shorthand);
+            }
+            if let Some(url) = &error.doc_url {
+                let _ = write!(out, "\n    .doc_url({:?})", url);
+            }
+            out.push_str(";\n");
+        }
+
+        out.push_str("\n/// All the errors, in order of definition.");
+        let _ = write!(out, "\npub const ALL: [&::terror::catalog::ErrorDef; {}] = [", self.errors.len());
+        for error in &self.errors {
+            let _ = write!(out, "\n    &{},", const_name(&error.code));
+        }
+        out.push_str("\n];\n");
+
+        out.push_str("\n/// Codes of the errors.");
+        let _ = write!(out, "\n#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]\npub enum {} {{", enum_name);
+        for error in &self.errors {
+            let _ = write!(out, "\n    /// {}\n    {},", doc_line(&error.message), variant_name(&error.code));
+        }
+        out.push_str("\n}\n");
+
+        let _ = write!(out, "\nimpl {} {{\n", enum_name);
+        out.push_str("\n    /// Looks up the definition of the error.");
+        out.push_str("\n    pub const fn def(self) -> &'static ::terror::catalog::ErrorDef {\n        match self {");
+        for error in &self.errors {
+            let _ = write!(
+                out,
+                "\n            {}::{} => &{},",
+                enum_name, variant_name(&error.code), const_name(&error.code)
+            );
+        }
+        out.push_str("\n        }\n    }\n");
+        out.push_str("\n    /// Constructs a new builder of the error.");
+        out.push_str("\n    pub fn builder(self) -> ::terror::Builder {\n");
+        out.push_str("        ::terror::Terror::from_def(self.def())\n    }\n");
+        out.push_str("\n}\n");
+        out
+    }
+
+    /// Generates a Markdown reference page
+    /// with the provided title.
+    pub fn to_markdown(&self, title: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", title);
+        out.push_str("| Code | Status | Short message | Message | Description |\n");
+        out.push_str("|:-----|:------:|:--------------|:--------|:------------|\n");
+        for error in &self.errors {
+            let code = match &error.doc_url {
+                Some(url) => format!("[`{}`]({})", escape_cell(&error.code), escape_cell(url)),
+                None => format!("`{}`", escape_cell(&error.code))
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                code,
+                error.status,
+                escape_cell(error.shorthand.as_deref().unwrap_or("")),
+                escape_cell(&error.message),
+                escape_cell(error.description.as_deref().unwrap_or(""))
+            );
+        }
+        out
+    }
+
+}
+
+/// Codes, whose identifiers are taken: `self` makes
+/// the `Self` variant, and `all` the `ALL` constant.
+const RESERVED: [&str; 2] = ["self", "all"];
+
+/// Splits the code into lowercase words
+/// at non-alphanumeric characters.
+fn words_of(code: &str) -> Vec<String> {
+    code.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+/// Names the constant, e.g. `USER_MISSING`.
+fn const_name(code: &str) -> String {
+    words_of(code).join("_").to_ascii_uppercase()
+}
+
+/// Names the enum variant, e.g. `UserMissing`.
+fn variant_name(code: &str) -> String {
+    words_of(code).iter()
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect()
+}
+
+/// Fits the text into a single doc comment line.
+fn doc_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+/// Escapes a Markdown table cell.
+fn escape_cell(text: &str) -> String {
+    doc_line(text)
+        .replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod codegen_test {
+    use crate::codegen::{CodegenError, Definitions};
+
+    type R = anyhow::Result<()>;
+
+    const YAML: &str = r#"
+errors:
+  - code: user.missing
+    status: 404
+    shorthand: Not found
+    message: no such user
+    doc_url: https://docs.example.com/errors/user.missing
+    description: The user was deleted | never existed.
+  - code: user.exists
+    status: 409
+    message: user "exists"
+"#;
+
+    const TOML: &str = r#"
+[[errors]]
+code = "user.missing"
+status = 404
+shorthand = "Not found"
+message = "no such user"
+doc_url = "https://docs.example.com/errors/user.missing"
+description = "The user was deleted | never existed."
+
+[[errors]]
+code = "user.exists"
+status = 409
+message = 'user "exists"'
+"#;
+
+    #[test]
+    fn parse_yaml_and_toml() -> R {
+        let from_yaml = Definitions::from_yaml(YAML)?;
+        let from_toml = Definitions::from_toml(TOML)?;
+
+        assert_eq!(from_yaml, from_toml);
+        assert_eq!(2, from_yaml.errors().len());
+        assert_eq!("user.missing", from_yaml.errors()[0].code);
+        assert_eq!(Some(String::from("Not found")), from_yaml.errors()[0].shorthand);
+        assert_eq!(None, from_yaml.errors()[1].doc_url);
+        Ok(())
+    }
+
+    #[test]
+    fn generate_rust() -> R {
+        let generated = Definitions::from_yaml(YAML)?.to_rust("ApiError");
+
+        assert!(generated.contains(
+            "pub const USER_MISSING: ::terror::catalog::ErrorDef = \
+            ::terror::catalog::ErrorDef::new(\"user.missing\", 404, \"no such user\")\n    \
+            .shorthand(\"Not found\")\n    \
+            .doc_url(\"https://docs.example.com/errors/user.missing\");"
+        ));
+        assert!(generated.contains(
+            "::terror::catalog::ErrorDef::new(\"user.exists\", 409, \"user \\\"exists\\\"\");"
+        ));
+        assert!(generated.contains("pub const ALL: [&::terror::catalog::ErrorDef; 2] = ["));
+        assert!(generated.contains("pub enum ApiError {"));
+        assert!(generated.contains("ApiError::UserExists => &USER_EXISTS,"));
+        assert!(generated.contains("pub fn builder(self) -> ::terror::Builder {"));
+        Ok(())
+    }
+
+    #[test]
+    fn generate_markdown() -> R {
+        let generated = Definitions::from_yaml(YAML)?.to_markdown("Errors");
+
+        assert!(generated.starts_with("# Errors\n\n| Code |"));
+        assert!(generated.contains(
+            "| [`user.missing`](https://docs.example.com/errors/user.missing) | 404 | Not found | \
+            no such user | The user was deleted \\| never existed. |"
+        ));
+        assert!(generated.contains("| `user.exists` | 409 |  | user \"exists\" |  |"));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_duplicate_codes() {
+        let input = r#"
+errors:
+  - { code: user.missing, status: 404, message: no such user }
+  - { code: user_missing, status: 410, message: user gone }
+"#;
+
+        let err = Definitions::from_yaml(input).unwrap_err();
+        assert!(matches!(err, CodegenError::DuplicateCode { code } if code == "user_missing"));
+    }
+
+    #[test]
+    fn reject_invalid_codes() {
+        for code in ["...", "404.user", "self", "all", "ALL"] {
+            let input = format!("errors:\n  - {{ code: '{}', status: 404, message: no such user }}", code);
+            let err = Definitions::from_yaml(&input).unwrap_err();
+            assert!(matches!(err, CodegenError::InvalidCode { .. }), "accepted {}", code);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_statuses() {
+        for status in [0, 99, 600] {
+            let input = format!("errors:\n  - {{ code: user.missing, status: {}, message: no such user }}", status);
+            let err = Definitions::from_yaml(&input).unwrap_err();
+            assert!(
+                matches!(err, CodegenError::InvalidStatus { status: rejected, .. } if rejected == status),
+                "accepted {}", status
+            );
+        }
+    }
+
+    #[test]
+    fn escape_markdown_cells() -> R {
+        let input = r#"
+errors:
+  - code: user|missing
+    status: 404
+    message: no such user
+    doc_url: https://docs.example.com/errors?code=user|missing
+"#;
+
+        let generated = Definitions::from_yaml(input)?.to_markdown("Errors");
+        assert!(generated.contains(
+            "| [`user\\|missing`](https://docs.example.com/errors?code=user\\|missing) | 404 |"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_unknown_format() {
+        let err = Definitions::load("errors.json").unwrap_err();
+
+        assert_eq!("unknown format of 'errors.json'", err.to_string());
+    }
+
+}
@@ -0,0 +1,381 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
+use serde::de::DeserializeOwned;
+use serde::ser::{self, Impossible, Serializer};
+use serde::Serialize as Serializable;
+use serde_json::{Map, Value};
+
+use crate::Builder;
+
+/// Payload of [Terror::details](crate::Terror::details).
+///
+/// By default, details are arbitrary JSON values by
+/// name. APIs with a fixed details schema may opt into
+/// a type of their own, which is then checked at
+/// compile time; see [Builder::details]. Any type, which
+/// implements [Serialize](serde::Serialize) and
+/// [Deserialize](serde::Deserialize), will do.
+///
+/// Details are omitted from the serialised object if
+/// they serialise into `null` or an empty object. If
+/// absent from the input, they are deserialised from
+/// `null` or, failing that, from an empty object, so
+/// that e.g. [Option]s or types with `#[serde(default)]`
+/// may be absent, while the rest are required.
+///
+/// ### Examples
+///
+/// ```rust
+/// use serde_derive::{Deserialize, Serialize};
+/// use terror::Terror;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Quota {
+///     limit: u32
+/// }
+///
+/// let inbound = r#"{ "status": 429, "message": "too many requests", "details": { "limit": 100 } }"#;
+/// let as_struct: Terror<Quota> = serde_json::from_str(inbound).unwrap();
+/// assert_eq!(100, as_struct.details.limit);
+///
+/// let inbound = r#"{ "status": 429, "message": "too many requests" }"#;
+/// assert!(serde_json::from_str::<Terror<Quota>>(inbound).is_err());
+/// assert!(serde_json::from_str::<Terror<Option<Quota>>>(inbound).is_ok());
+/// ```
+pub trait Details: Serializable + DeserializeOwned {}
+
+impl<T: Serializable + DeserializeOwned> Details for T {}
+
+/// Tells whether the details serialise into
+/// nothing to report: `null` or an empty object.
+///
+/// Only probes the shape of the details, stopping
+/// at the first entry, rather than serialising them.
+pub(crate) fn is_empty<D: Serializable>(details: &D) -> bool {
+    details.serialize(Probe).is_ok()
+}
+
+/// [Serializer], which succeeds for values serialised
+/// into `null` or an empty object, and fails otherwise.
+struct Probe;
+
+/// Failure of [Probe]: there is something to report.
+#[derive(Debug)]
+struct NotEmpty;
+
+impl Display for NotEmpty {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "not empty")
+    }
+}
+
+impl Error for NotEmpty {}
+
+impl ser::Error for NotEmpty {
+    fn custom<T: Display>(_: T) -> Self {
+        NotEmpty
+    }
+}
+
+impl Serializer for Probe {
+    type Ok = ();
+    type Error = NotEmpty;
+    type SerializeSeq = Impossible<(), NotEmpty>;
+    type SerializeTuple = Impossible<(), NotEmpty>;
+    type SerializeTupleStruct = Impossible<(), NotEmpty>;
+    type SerializeTupleVariant = Impossible<(), NotEmpty>;
+    type SerializeMap = Probe;
+    type SerializeStruct = Probe;
+    type SerializeStructVariant = Impossible<(), NotEmpty>;
+
+    fn serialize_bool(self, _: bool) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_i8(self, _: i8) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_i16(self, _: i16) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_i32(self, _: i32) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_i64(self, _: i64) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_u8(self, _: u8) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_u16(self, _: u16) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_u32(self, _: u32) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_u64(self, _: u64) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_f32(self, _: f32) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_f64(self, _: f64) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_char(self, _: char) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_str(self, _: &str) -> Result<(), NotEmpty> { Err(NotEmpty) }
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), NotEmpty> { Err(NotEmpty) }
+
+    fn serialize_none(self) -> Result<(), NotEmpty> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serializable + ?Sized>(self, value: &T) -> Result<(), NotEmpty> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), NotEmpty> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), NotEmpty> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<(), NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_newtype_struct<T: Serializable + ?Sized>(self, _: &'static str, value: &T) -> Result<(), NotEmpty> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serializable + ?Sized>(self,
+                                                           _: &'static str,
+                                                           _: u32,
+                                                           _: &'static str,
+                                                           _: &T) -> Result<(), NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_tuple_variant(self,
+                               _: &'static str,
+                               _: u32,
+                               _: &'static str,
+                               _: usize) -> Result<Self::SerializeTupleVariant, NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Probe, NotEmpty> {
+        match len {
+            Some(0) | None => Ok(Probe),
+            Some(_) => Err(NotEmpty)
+        }
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Probe, NotEmpty> {
+        Ok(Probe)
+    }
+
+    fn serialize_struct_variant(self,
+                                _: &'static str,
+                                _: u32,
+                                _: &'static str,
+                                _: usize) -> Result<Self::SerializeStructVariant, NotEmpty> {
+        Err(NotEmpty)
+    }
+}
+
+impl ser::SerializeMap for Probe {
+    type Ok = ();
+    type Error = NotEmpty;
+
+    fn serialize_key<T: Serializable + ?Sized>(&mut self, _: &T) -> Result<(), NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn serialize_value<T: Serializable + ?Sized>(&mut self, _: &T) -> Result<(), NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn end(self) -> Result<(), NotEmpty> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for Probe {
+    type Ok = ();
+    type Error = NotEmpty;
+
+    fn serialize_field<T: Serializable + ?Sized>(&mut self, _: &'static str, _: &T) -> Result<(), NotEmpty> {
+        Err(NotEmpty)
+    }
+
+    fn end(self) -> Result<(), NotEmpty> {
+        Ok(())
+    }
+}
+
+/// Restores the details absent from the input
+/// from `null` or, failing that, an empty object.
+pub(crate) fn absent<D: DeserializeOwned>() -> Option<D> {
+    D::deserialize(Value::Null).ok()
+        .or_else(|| D::deserialize(Value::Object(Map::new())).ok())
+}
+
+/// Failure to serialise a detail via
+/// [Builder::try_add_struct_detail].
+///
+/// Hands the builder back, so that the error
+/// report may still be completed.
+///
+/// ### Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use terror::Terror;
+///
+/// let weights = HashMap::from([((1, 2), 0.5)]);
+/// let built = Terror::new(500, "generic error")
+///     .try_add_struct_detail("weights", weights)
+///     .unwrap_or_else(|err| err.into_builder())
+///     .build();
+/// assert!(built.details.is_empty());
+/// ```
+pub struct DetailError {
+    name: String,
+    cause: serde_json::Error,
+    builder: Box<Builder>
+}
+
+impl DetailError {
+
+    pub(crate) fn new(name: String, cause: serde_json::Error, builder: Builder) -> DetailError {
+        DetailError { name, cause, builder: Box::new(builder) }
+    }
+
+    /// Reports the name of the detail,
+    /// which failed to serialise.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the builder as it was
+    /// before the failed call.
+    pub fn into_builder(self) -> Builder {
+        *self.builder
+    }
+
+    pub(crate) fn into_parts(self) -> (String, serde_json::Error, Builder) {
+        (self.name, self.cause, *self.builder)
+    }
+
+}
+
+impl Debug for DetailError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DetailError")
+            .field("name", &self.name)
+            .field("cause", &self.cause)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Display for DetailError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to serialise detail '{}': {}", self.name, self.cause)
+    }
+}
+
+impl Error for DetailError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+/// Failure to retrieve a detail via
+/// [Terror::detail](crate::Terror::detail) or
+/// [Terror::detail_at](crate::Terror::detail_at).
+#[derive(Debug)]
+pub enum LookupError {
+
+    /// There is no value under the key
+    Missing {
+
+        /// Detail name or pointer looked up
+        key: String
+
+    },
+
+    /// The value under the key does
+    /// not fit the requested type
+    Mistyped {
+
+        /// Detail name or pointer looked up
+        key: String,
+
+        /// Failure to deserialise the value
+        /// as the requested type
+        cause: serde_json::Error
+
+    }
+
+}
+
+impl LookupError {
+
+    /// Reports the detail name or
+    /// the pointer looked up.
+    pub fn key(&self) -> &str {
+        match self {
+            LookupError::Missing { key } => key,
+            LookupError::Mistyped { key, .. } => key
+        }
+    }
+
+}
+
+impl Display for LookupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LookupError::Missing { key } => write!(f, "missing detail '{}'", key),
+            LookupError::Mistyped { key, cause } => write!(f, "mistyped detail '{}': {}", key, cause)
+        }
+    }
+}
+
+impl Error for LookupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LookupError::Missing { .. } => None,
+            LookupError::Mistyped { cause, .. } => Some(cause)
+        }
+    }
+}
+
+#[cfg(test)]
+mod detail_test {
+    use std::collections::HashMap;
+    use serde_derive::Serialize;
+    use serde_json::{json, Value};
+    use crate::detail::is_empty;
+
+    #[test]
+    fn probe_emptiness() {
+        #[derive(Serialize)]
+        struct Quota {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            limit: Option<u32>
+        }
+
+        #[derive(Serialize)]
+        struct Unit;
+
+        assert!(is_empty(&HashMap::<String, Value>::new()));
+        assert!(is_empty(&json!({})));
+        assert!(is_empty(&Value::Null));
+        assert!(is_empty(&None::<Quota>));
+        assert!(is_empty(&Quota { limit: None }));
+        assert!(is_empty(&Some(Quota { limit: None })));
+        assert!(is_empty(&Unit));
+        assert!(is_empty(&()));
+
+        assert!(!is_empty(&HashMap::from([("limit", 100)])));
+        assert!(!is_empty(&json!({ "limit": 100 })));
+        assert!(!is_empty(&Quota { limit: Some(100) }));
+        assert!(!is_empty(&json!([])));
+        assert!(!is_empty(&json!("")));
+        assert!(!is_empty(&0));
+    }
+
+}
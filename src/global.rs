@@ -0,0 +1,31 @@
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// A process-wide slot holding a replaceable
+/// strategy, e.g. an [IdGenerator](crate::id::IdGenerator);
+/// empty until one is installed, in which case
+/// the caller falls back to its default.
+pub(crate) struct Global<T: ?Sized>(RwLock<Option<Arc<T>>>);
+
+impl<T: ?Sized> Global<T> {
+
+    /// Constructs an empty slot.
+    pub(crate) const fn new() -> Global<T> {
+        Global(RwLock::new(None))
+    }
+
+    /// Replaces the content of the slot.
+    pub(crate) fn set(&self, value: Arc<T>) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Some(value);
+    }
+
+    /// Empties the slot.
+    pub(crate) fn reset(&self) {
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    /// Returns the content of the slot, if any.
+    pub(crate) fn get(&self) -> Option<Arc<T>> {
+        self.0.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+}
@@ -0,0 +1,555 @@
+//! gRPC interoperability for feature `tonic`.
+//!
+//! [Terror] converts from and into [tonic::Status],
+//! so that errors survive a hop between JSON and
+//! gRPC services.
+//!
+//! ### Mapping
+//!
+//! The HTTP status is mapped onto a gRPC [Code] with
+//! [code_for_status] and back with [status_for_code],
+//! following the conventions of `google.rpc.Code`.
+//! [Terror::message] becomes the status message.
+//!
+//! Everything else travels in the status details:
+//! * `google.rpc.ErrorInfo` - the [reason](reason_for),
+//!   the [domain](ERROR_DOMAIN) and metadata entries under
+//!   keys prefixed with `terror-`: [Terror::details] as
+//!   JSON under `terror-detail-<name>`, along with the
+//!   exact HTTP status, `error_code`, `short_message`,
+//!   `violations`, `upstream`, `errors`, `template` and,
+//!   if enabled by features, `id`, `timestamp` and
+//!   `reference`, unless they are
+//!   [placeholders](crate::strict);
+//! * `google.rpc.BadRequest` - [Terror::violations], for
+//!   the sake of clients unaware of `terror`; as it only
+//!   keeps the field and the message of each, violations
+//!   are restored from it if the metadata entry is absent.
+//!
+//! As `google.rpc.ErrorInfo` requires the reason to be
+//! `UPPER_SNAKE_CASE` and metadata keys to consist of
+//! letters, digits, `-` and `_`, the reason is derived
+//! from the error code, which travels as is in metadata,
+//! and other characters of detail names are escaped as
+//! `-` followed by two hex digits of each byte.
+//!
+//! As the exact HTTP status is carried along, a round
+//! trip restores it even where several statuses share
+//! a gRPC code. Statuses without `ErrorInfo` of the
+//! [domain](ERROR_DOMAIN), e.g. produced by other
+//! services, are converted by code and message, with
+//! the reason of their `ErrorInfo`, if any, taken for
+//! the error code and its metadata entries for string
+//! details as they are. Metadata entries without the
+//! `terror-` prefix, e.g. set by proxies, are taken for
+//! string details as they are either way.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::Terror;
+//! use tonic::{Code, Status};
+//!
+//! let status: Status = Terror::new(404, "no such user")
+//!     .error_code("user.missing")
+//!     .build()
+//!     .into();
+//! assert_eq!(Code::NotFound, status.code());
+//!
+//! let back: Terror = status.into();
+//! assert_eq!(Some(String::from("user.missing")), back.error_code);
+//! ```
+
+use std::collections::HashMap;
+use serde::Serialize as Serializable;
+use serde_json::Value;
+use tonic::{Code, Status};
+use tonic_types::{ErrorDetails, FieldViolation, StatusExt};
+
+#[cfg(any(feature = "time", feature = "time_crate"))]
+use crate::timestamp;
+
+use crate::batch::ItemError;
+use crate::{Template, Terror, Violation};
+
+/// `ErrorInfo` domain of converted errors.
+pub const ERROR_DOMAIN: &str = "terror";
+
+/// Prefix of metadata entries set by this crate.
+const RESERVED_PREFIX: &str = "terror-";
+
+/// Prefix of metadata entries carrying [Terror::details].
+const DETAIL_PREFIX: &str = "terror-detail-";
+
+/// Metadata entry carrying [Terror::status].
+const STATUS_KEY: &str = "terror-status";
+
+/// Metadata entry carrying [Terror::error_code].
+const ERROR_CODE_KEY: &str = "terror-error-code";
+
+/// Metadata entry carrying [Terror::short_message].
+const SHORT_MESSAGE_KEY: &str = "terror-short-message";
+
+/// Metadata entry carrying [Terror::violations].
+const VIOLATIONS_KEY: &str = "terror-violations";
+
+/// Metadata entry carrying [Terror::upstream].
+const UPSTREAM_KEY: &str = "terror-upstream";
+
+/// Metadata entry carrying [Terror::errors].
+const ERRORS_KEY: &str = "terror-errors";
+
+/// Metadata entry carrying [Terror::template].
+const TEMPLATE_KEY: &str = "terror-template";
+
+/// Metadata entry carrying the error ID.
+#[cfg(feature = "err_id")]
+const ID_KEY: &str = "terror-id";
+
+/// Metadata entry carrying the timestamp.
+#[cfg(any(feature = "time", feature = "time_crate"))]
+const TIMESTAMP_KEY: &str = "terror-timestamp";
+
+/// Metadata entry carrying the reference.
+#[cfg(feature = "mdn")]
+const REFERENCE_KEY: &str = "terror-reference";
+
+/// Maps an HTTP status onto a gRPC code.
+///
+/// Statuses without a dedicated code are mapped
+/// by class: other `4xx` onto `FailedPrecondition`,
+/// other `5xx` onto `Internal` and the rest onto
+/// `Unknown`.
+///
+/// ### Examples
+///
+/// ```rust
+/// use tonic::Code;
+/// assert_eq!(Code::ResourceExhausted, terror::grpc::code_for_status(429));
+/// ```
+pub fn code_for_status(status: u16) -> Code {
+    match status {
+        200..=299 => Code::Ok,
+        400 | 422 => Code::InvalidArgument,
+        401 => Code::Unauthenticated,
+        403 => Code::PermissionDenied,
+        404 => Code::NotFound,
+        408 | 504 => Code::DeadlineExceeded,
+        409 => Code::AlreadyExists,
+        412 => Code::FailedPrecondition,
+        416 => Code::OutOfRange,
+        429 => Code::ResourceExhausted,
+        499 => Code::Cancelled,
+        501 => Code::Unimplemented,
+        503 => Code::Unavailable,
+        400..=499 => Code::FailedPrecondition,
+        500..=599 => Code::Internal,
+        _ => Code::Unknown
+    }
+}
+
+/// Maps a gRPC code onto an HTTP status.
+///
+/// ### Examples
+///
+/// ```rust
+/// use tonic::Code;
+/// assert_eq!(409, terror::grpc::status_for_code(Code::Aborted));
+/// ```
+pub fn status_for_code(code: Code) -> u16 {
+    match code {
+        Code::Ok => 200,
+        Code::Cancelled => 499,
+        Code::InvalidArgument
+        | Code::FailedPrecondition
+        | Code::OutOfRange => 400,
+        Code::Unauthenticated => 401,
+        Code::PermissionDenied => 403,
+        Code::NotFound => 404,
+        Code::AlreadyExists
+        | Code::Aborted => 409,
+        Code::ResourceExhausted => 429,
+        Code::Unimplemented => 501,
+        Code::Unavailable => 503,
+        Code::DeadlineExceeded => 504,
+        Code::Unknown
+        | Code::Internal
+        | Code::DataLoss => 500
+    }
+}
+
+/// Derives the `ErrorInfo` reason, which must match
+/// `[A-Z][A-Z0-9_]+`, from the error code: letters are
+/// upper-cased, digits kept and other characters replaced
+/// with `_`. Codes, which do not start with a letter, get
+/// prefixed with `CODE_`; errors without a code are
+/// reported as `HTTP_<status>`.
+///
+/// ### Examples
+///
+/// ```rust
+/// use terror::Terror;
+/// let built = Terror::new(404, "no such user")
+///     .error_code("user.missing")
+///     .build();
+/// assert_eq!("USER_MISSING", terror::grpc::reason_for(&built));
+/// ```
+pub fn reason_for(terror: &Terror) -> String {
+    let code = match &terror.error_code {
+        Some(code) if !code.is_empty() => code,
+        _ => return format!("HTTP_{}", terror.status)
+    };
+    let reason: String = code.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c.to_ascii_uppercase(),
+            false => '_'
+        })
+        .collect();
+    match reason.starts_with(|c: char| c.is_ascii_alphabetic()) && reason.len() > 1 {
+        true => reason,
+        false => format!("CODE_{}", reason)
+    }
+}
+
+/// Escapes the detail name into a valid metadata key suffix.
+fn escape_detail(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("-{:02x}", byte))
+        }
+    }
+    escaped
+}
+
+/// Restores the detail name escaped by [escape_detail];
+/// malformed escapes are kept as they are.
+fn unescape_detail(escaped: &str) -> String {
+    let mut bytes = Vec::with_capacity(escaped.len());
+    let mut rest = escaped.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let decoded = match byte {
+            b'-' => tail.get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None
+        };
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            },
+            None => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Serialises a [Terror] field as JSON into a metadata
+/// entry; never fails, same as for [Problem](crate::Problem)
+/// members.
+fn entry<T: Serializable>(field: &T) -> String {
+    serde_json::to_string(field)
+        .expect("terror fields always serialise into JSON")
+}
+
+impl From<Terror> for Status {
+    fn from(terror: Terror) -> Self {
+        let mut metadata: HashMap<String, String> = terror.details.iter()
+            .map(|(key, value)| (format!("{}{}", DETAIL_PREFIX, escape_detail(key)), value.to_string()))
+            .collect();
+        metadata.insert(String::from(STATUS_KEY), terror.status.to_string());
+        if let Some(code) = &terror.error_code {
+            metadata.insert(String::from(ERROR_CODE_KEY), code.clone());
+        }
+        if let Some(shorthand) = &terror.short_message {
+            metadata.insert(String::from(SHORT_MESSAGE_KEY), shorthand.clone());
+        }
+        if !terror.violations.is_empty() {
+            metadata.insert(String::from(VIOLATIONS_KEY), entry(&terror.violations));
+        }
+        if !terror.upstream.is_empty() {
+            metadata.insert(String::from(UPSTREAM_KEY), entry(&terror.upstream));
+        }
+        if !terror.errors.is_empty() {
+            metadata.insert(String::from(ERRORS_KEY), entry(&terror.errors));
+        }
+        if let Some(template) = &terror.template {
+            metadata.insert(String::from(TEMPLATE_KEY), entry(template));
+        }
+
+        #[cfg(feature = "err_id")]
+        if !terror.id.is_nil() {
+            metadata.insert(String::from(ID_KEY), terror.id.to_string());
+        }
+
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        if !timestamp::is_epoch(&terror.timestamp) {
+            metadata.insert(
+                String::from(TIMESTAMP_KEY),
+                timestamp::to_value(&terror.timestamp).to_string()
+            );
+        }
+
+        #[cfg(feature = "mdn")]
+        if !terror.reference.is_empty() {
+            metadata.insert(String::from(REFERENCE_KEY), terror.reference.clone());
+        }
+
+        let reason = reason_for(&terror);
+        let mut details = ErrorDetails::with_error_info(reason, ERROR_DOMAIN, metadata);
+        if !terror.violations.is_empty() {
+            let violations: Vec<FieldViolation> = terror.violations.iter()
+                .map(|violation| FieldViolation::new(violation.field.clone(), violation.message.clone()))
+                .collect();
+            details.set_bad_request(violations);
+        }
+
+        Status::with_error_details(code_for_status(terror.status), terror.message, details)
+    }
+}
+
+impl From<Status> for Terror {
+    fn from(status: Status) -> Self {
+        let details = status.get_error_details();
+        let (mut metadata, foreign) = match details.error_info() {
+            Some(info) if info.domain == ERROR_DOMAIN => (info.metadata.clone(), HashMap::new()),
+            Some(info) => (HashMap::new(), info.metadata.clone()),
+            None => (HashMap::new(), HashMap::new())
+        };
+
+        let code = metadata.remove(STATUS_KEY)
+            .and_then(|code| code.parse::<u16>().ok())
+            .unwrap_or_else(|| status_for_code(status.code()));
+        let mut builder = Terror::new(code, status.message());
+
+        if let Some(shorthand) = metadata.remove(SHORT_MESSAGE_KEY) {
+            builder = builder.shorthand(shorthand);
+        }
+        match details.error_info() {
+            Some(info) if info.domain == ERROR_DOMAIN => {
+                if let Some(code) = metadata.remove(ERROR_CODE_KEY) {
+                    builder = builder.error_code(code);
+                }
+            },
+            Some(info) if !info.reason.is_empty() => builder = builder.error_code(info.reason.clone()),
+            _ => {}
+        }
+        let violations = metadata.remove(VIOLATIONS_KEY)
+            .and_then(|violations| serde_json::from_str::<Vec<Violation>>(&violations).ok());
+        match (violations, details.bad_request()) {
+            (Some(violations), _) => builder = builder.add_violations(violations),
+            (None, Some(bad_request)) => builder = builder.add_violations(bad_request.field_violations.iter()
+                .map(|violation| Violation::new(violation.field.clone(), violation.description.clone()))),
+            (None, None) => {}
+        }
+        if let Some(upstream) = metadata.remove(UPSTREAM_KEY)
+            .and_then(|upstream| serde_json::from_str::<Vec<Terror>>(&upstream).ok()) {
+            builder = builder.add_upstreams(upstream);
+        }
+        if let Some(errors) = metadata.remove(ERRORS_KEY)
+            .and_then(|errors| serde_json::from_str::<Vec<ItemError>>(&errors).ok()) {
+            builder = builder.add_item_errors(errors);
+        }
+        builder.template = metadata.remove(TEMPLATE_KEY)
+            .and_then(|template| serde_json::from_str::<Template>(&template).ok());
+
+        #[cfg(feature = "err_id")]
+        if let Some(id) = metadata.remove(ID_KEY).and_then(|id| id.parse().ok()) {
+            builder = builder.id(id);
+        }
+
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        if let Some(ts) = metadata.remove(TIMESTAMP_KEY)
+            .and_then(|ts| serde_json::from_str(&ts).ok())
+            .and_then(timestamp::from_value) {
+            builder = builder.timestamp(ts);
+        }
+
+        #[cfg(feature = "mdn")]
+        if let Some(reference) = metadata.remove(REFERENCE_KEY) {
+            builder = builder.reference(reference);
+        }
+
+        for (key, value) in metadata {
+            if let Some(name) = key.strip_prefix(DETAIL_PREFIX) {
+                let value = serde_json::from_str(&value)
+                    .unwrap_or(Value::String(value));
+                builder = builder.add_value_detail(unescape_detail(name), value);
+            } else if !key.starts_with(RESERVED_PREFIX) {
+                builder = builder.add_text_detail(key, value);
+            }
+        }
+        for (key, value) in foreign {
+            builder = builder.add_text_detail(key, value);
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod grpc_test {
+    use serde_json::json;
+    use tonic::{Code, Status};
+    use tonic_types::{ErrorDetails, StatusExt};
+    use crate::grpc::{code_for_status, escape_detail, reason_for, status_for_code, unescape_detail, ERROR_DOMAIN};
+    use crate::batch::ItemError;
+    use crate::{MissingKey, Terror, Violation};
+
+    #[test]
+    fn map_status_to_code() {
+        assert_eq!(Code::InvalidArgument, code_for_status(400));
+        assert_eq!(Code::NotFound, code_for_status(404));
+        assert_eq!(Code::AlreadyExists, code_for_status(409));
+        assert_eq!(Code::ResourceExhausted, code_for_status(429));
+        assert_eq!(Code::FailedPrecondition, code_for_status(418));
+        assert_eq!(Code::Unavailable, code_for_status(503));
+        assert_eq!(Code::Internal, code_for_status(507));
+        assert_eq!(Code::Unknown, code_for_status(999));
+    }
+
+    #[test]
+    fn map_code_to_status() {
+        assert_eq!(404, status_for_code(Code::NotFound));
+        assert_eq!(409, status_for_code(Code::AlreadyExists));
+        assert_eq!(409, status_for_code(Code::Aborted));
+        assert_eq!(429, status_for_code(Code::ResourceExhausted));
+        assert_eq!(500, status_for_code(Code::DataLoss));
+        assert_eq!(504, status_for_code(Code::DeadlineExceeded));
+    }
+
+    #[test]
+    fn into_status() {
+        let built = Terror::new(409, "version conflict")
+            .error_code("entity.conflict")
+            .add_bool_detail("retryable", false)
+            .add_violation(Violation::new("version", "is stale").code("stale"))
+            .build();
+
+        let status: Status = built.into();
+        assert_eq!(Code::AlreadyExists, status.code());
+        assert_eq!("version conflict", status.message());
+
+        let details = status.get_error_details();
+        let info = details.error_info().unwrap();
+        assert_eq!("ENTITY_CONFLICT", info.reason);
+        assert_eq!(ERROR_DOMAIN, info.domain);
+        assert_eq!(Some(&String::from("entity.conflict")), info.metadata.get("terror-error-code"));
+        assert_eq!(Some(&String::from("false")), info.metadata.get("terror-detail-retryable"));
+        assert!(!info.metadata.contains_key("retryable"));
+        assert_eq!(Some(&String::from("409")), info.metadata.get("terror-status"));
+
+        let violations = &details.bad_request().unwrap().field_violations;
+        assert_eq!(1, violations.len());
+        assert_eq!("version", violations[0].field);
+        assert_eq!("is stale", violations[0].description);
+    }
+
+    #[test]
+    fn derive_reason() {
+        let reason = |code: Option<&str>| {
+            let mut built = Terror::new(404, "no such user").build();
+            built.error_code = code.map(String::from);
+            reason_for(&built)
+        };
+
+        assert_eq!("USER_MISSING", reason(Some("user.missing")));
+        assert_eq!("USER_NOT_FOUND_2", reason(Some("user-not-found/2")));
+        assert_eq!("CODE_404_USER", reason(Some("404.user")));
+        assert_eq!("CODE_A", reason(Some("a")));
+        assert_eq!("HTTP_404", reason(None));
+        assert_eq!("HTTP_404", reason(Some("")));
+    }
+
+    #[test]
+    fn escape_detail_names() {
+        for name in ["retry_after", "retry-after", "user.id", "Größe", "-2d", ""] {
+            let escaped = escape_detail(name);
+            assert!(escaped.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+            assert_eq!(name, unescape_detail(&escaped));
+        }
+        assert_eq!("retry_after", escape_detail("retry_after"));
+        assert_eq!("user-2eid", escape_detail("user.id"));
+        assert_eq!("-zz", unescape_detail("-zz"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let original = Terror::new(422, "invalid user")
+            .shorthand("Invalid")
+            .error_code("user.invalid")
+            .add_text_detail("user", "alice")
+            .add_text_detail("terror-trace", "abc")
+            .add_text_detail("user.name", "Alice")
+            .add_int_detail("attempt", 3)
+            .add_value_detail("limits", json!({ "age": [0, 150] }))
+            .add_upstream(Terror::new(400, "invalid age").build())
+            .add_item_error(ItemError::new("alice", Terror::new(400, "invalid age").build()))
+            .add_violation(
+                Violation::new("age", "must be positive")
+                    .code("range")
+                    .rejected_value(json!(-1))
+            )
+            .interpolate(MissingKey::Keep)
+            .keep_template()
+            .build();
+
+        let status: Status = original.clone().into();
+        let restored: Terror = status.into();
+
+        assert_eq!(original, restored);
+    }
+
+    #[test]
+    fn from_foreign_status() {
+        let plain: Terror = Status::unavailable("try later").into();
+        assert_eq!(503, plain.status);
+        assert_eq!("try later", plain.message);
+        assert!(plain.details.is_empty());
+
+        let detailed: Terror = Status::with_error_details(
+            Code::PermissionDenied,
+            "no access",
+            ErrorDetails::with_error_info(
+                "ACCESS_DENIED",
+                "example.com",
+                [
+                    (String::from("resource"), String::from("users/42")),
+                    (String::from("quota"), String::from("123")),
+                    (String::from("terror-status"), String::from("418"))
+                ]
+            )
+        ).into();
+        assert_eq!(403, detailed.status);
+        assert_eq!(Some(String::from("ACCESS_DENIED")), detailed.error_code);
+        assert_eq!(Some(&json!("users/42")), detailed.details.get("resource"));
+        assert_eq!(Some(&json!("123")), detailed.details.get("quota"));
+        assert_eq!(Some(&json!("418")), detailed.details.get("terror-status"));
+        assert_eq!(3, detailed.details.len());
+
+        let unknown: Terror = Status::with_error_details(
+            Code::NotFound,
+            "no such user",
+            ErrorDetails::with_error_info(
+                "USER_MISSING",
+                ERROR_DOMAIN,
+                [(String::from("terror-unknown"), String::from("true"))]
+            )
+        ).into();
+        assert_eq!(None, unknown.error_code);
+        assert!(unknown.details.is_empty());
+
+        let bad_request: Terror = Status::with_error_details(
+            Code::InvalidArgument,
+            "invalid user",
+            ErrorDetails::with_bad_request_violation("age", "must be positive")
+        ).into();
+        assert_eq!(400, bad_request.status);
+        assert_eq!(vec![Violation::new("age", "must be positive")], bad_request.violations);
+    }
+
+}
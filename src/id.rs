@@ -0,0 +1,198 @@
+//! Error ID generation for feature `err_id`.
+//!
+//! By default, [Terror](crate::Terror) is assigned
+//! a random [V4] UUID. Another [IdGenerator] may be
+//! installed process-wide via [set_generator], or
+//! used for a single error via
+//! [Builder::id_generator](crate::Builder::id_generator).
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::id::{Sequential, V7};
+//! use terror::Terror;
+//!
+//! terror::id::set_generator(V7);
+//!
+//! let ids = Sequential::new();
+//! let built = Terror::new(404, "no such user")
+//!     .id_generator(&ids)
+//!     .build();
+//! assert_eq!(1, built.id.as_u128());
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+use crate::global::Global;
+
+/// A strategy of error ID generation.
+pub trait IdGenerator: Send + Sync {
+
+    /// Produces a new error ID.
+    fn generate(&self) -> Uuid;
+
+}
+
+/// Generates random (version 4) UUIDs; the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct V4;
+
+impl IdGenerator for V4 {
+    fn generate(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Generates time-ordered (version 7) UUIDs,
+/// which sort by the moment of creation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct V7;
+
+impl IdGenerator for V7 {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// Generates [ULID](https://github.com/ulid/spec)s,
+/// which sort by the moment of creation.
+///
+/// Only the 128 bits of the ID are a ULID: it is
+/// still reported, and serialised, as a hyphenated
+/// UUID, whose version and variant bits are random,
+/// so it is _not_ a valid UUID of any version. Use
+/// `ulid::Ulid(id.as_u128())` to restore the ULID,
+/// e.g. for its Crockford base32 form.
+#[cfg(feature = "ulid")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ulid;
+
+#[cfg(feature = "ulid")]
+impl IdGenerator for Ulid {
+    fn generate(&self) -> Uuid {
+        Uuid::from_u128(ulid::Ulid::new().0)
+    }
+}
+
+/// Generates consecutive IDs: `...0001`, `...0002`
+/// and so on; intended for deterministic tests.
+#[derive(Debug, Default)]
+pub struct Sequential(AtomicU64);
+
+impl Sequential {
+
+    /// Constructs a generator starting at `1`.
+    pub fn new() -> Sequential {
+        Sequential::starting_at(1)
+    }
+
+    /// Constructs a generator starting
+    /// at the provided value.
+    pub fn starting_at(first: u64) -> Sequential {
+        Sequential(AtomicU64::new(first))
+    }
+
+}
+
+impl IdGenerator for Sequential {
+    fn generate(&self) -> Uuid {
+        Uuid::from_u128(self.0.fetch_add(1, Ordering::Relaxed) as u128)
+    }
+}
+
+/// Process-wide generator; [V4] if not set.
+static GENERATOR: Global<dyn IdGenerator> = Global::new();
+
+/// Installs the process-wide generator, which is
+/// used by [Terror::new](crate::Terror::new) from
+/// then on.
+pub fn set_generator<G: IdGenerator + 'static>(generator: G) {
+    GENERATOR.set(Arc::new(generator));
+}
+
+/// Restores the default process-wide generator.
+pub fn reset_generator() {
+    GENERATOR.reset();
+}
+
+/// Generates an ID with the process-wide generator.
+pub(crate) fn generate() -> Uuid {
+    match GENERATOR.get() {
+        Some(generator) => generator.generate(),
+        None => V4.generate()
+    }
+}
+
+#[cfg(test)]
+mod id_test {
+    use crate::id::{IdGenerator, Sequential, V4, V7};
+    use crate::Terror;
+
+    #[test]
+    fn generate_v4() {
+        assert_eq!(4, V4.generate().get_version_num());
+    }
+
+    #[test]
+    fn generate_v7() {
+        let first = V7.generate();
+        let second = V7.generate();
+
+        assert_eq!(7, first.get_version_num());
+        assert!(first < second);
+    }
+
+    #[test]
+    #[cfg(feature = "ulid")]
+    fn generate_ulid() {
+        use crate::id::Ulid;
+
+        let first = Ulid.generate();
+        let second = Ulid.generate();
+
+        assert_ne!(first, second);
+        assert!(first.as_u128() >> 80 <= second.as_u128() >> 80);
+    }
+
+    #[test]
+    #[cfg(feature = "ulid")]
+    fn restore_ulid() {
+        use crate::id::Ulid;
+
+        let id = Ulid.generate();
+        let restored = ulid::Ulid(id.as_u128());
+
+        assert_eq!(26, restored.to_string().len());
+        assert_eq!(Ok(restored), ulid::Ulid::from_string(&restored.to_string()));
+        assert_eq!(id, uuid::Uuid::from_u128(restored.0));
+    }
+
+    #[test]
+    fn generate_sequential() {
+        let ids = Sequential::starting_at(41);
+
+        assert_eq!(41, ids.generate().as_u128());
+        assert_eq!(42, ids.generate().as_u128());
+        assert_eq!(
+            "00000000-0000-0000-0000-00000000002b",
+            ids.generate().to_string()
+        );
+    }
+
+    #[test]
+    fn build_w_id_generator() {
+        let ids = Sequential::new();
+
+        let first = Terror::new(404, "generic error")
+            .id_generator(&ids)
+            .build();
+        let second = Terror::new(404, "generic error")
+            .id_generator(&ids)
+            .build();
+
+        assert_eq!(1, first.id.as_u128());
+        assert_eq!(2, second.id.as_u128());
+    }
+
+}
@@ -1,17 +1,76 @@
+//! Uniform REST error response body, tailored
+//! for JSON; see [Terror].
+//!
+//! ### Interoperability
+//!
+//! Services built with different feature sets can
+//! exchange error bodies: fields enabled by features
+//! may be absent when deserialising, see [strict], and
+//! timestamps are encoded the same way by either
+//! backend, see `terror::timestamp`.
+
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use serde_derive::{Serialize, Deserialize};
+use batch::ItemError;
+use catalog::ErrorDef;
+use strict::TerrorRepr;
+use template::Rendering;
 
-#[cfg(feature = "time")]
-use chrono::{DateTime, Utc};
+#[cfg(any(feature = "time", feature = "time_crate"))]
+use clock::Clock;
 #[cfg(feature = "infer_http")]
 use httpstatus::StatusCode;
+use serde::de::DeserializeOwned;
 use serde::Serialize as Serializable;
 use serde_json::{Number, Value};
 #[cfg(feature = "err_id")]
 use uuid::Uuid;
+#[cfg(feature = "err_id")]
+use id::IdGenerator;
+#[cfg(any(feature = "time", feature = "time_crate"))]
+use timestamp::Timestamp;
+
+pub mod batch;
+pub mod catalog;
+#[cfg(feature = "reqwest")]
+pub mod client;
+#[cfg(any(feature = "time", feature = "time_crate"))]
+pub mod clock;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+mod detail;
+#[cfg(any(feature = "err_id", feature = "time", feature = "time_crate"))]
+mod global;
+#[cfg(feature = "tonic")]
+pub mod grpc;
+#[cfg(feature = "err_id")]
+pub mod id;
+mod macros;
+#[cfg(feature = "utoipa")]
+pub mod openapi;
+mod problem;
+#[cfg(feature = "schemars")]
+pub mod schema;
+mod source;
+pub mod strict;
+mod template;
+#[cfg(any(feature = "time", feature = "time_crate"))]
+pub mod timestamp;
+pub mod upstream;
+mod violation;
+#[cfg(any(feature = "axum", feature = "actix"))]
+mod web;
+
+pub use detail::{DetailError, Details, LookupError};
+pub use problem::{Problem, PROBLEM_CONTENT_TYPE};
+pub use source::ErrorSource;
+pub use template::{MissingKey, Template, TemplateError};
+pub use violation::Violation;
+#[cfg(feature = "derive")]
+pub use terror_derive::IntoTerror;
 
 /// A buildable error object, which suits
 /// most cases of error reporting for web
@@ -27,29 +86,61 @@ use uuid::Uuid;
 /// * shortened version of error message;
 /// * specific error code;
 /// * arbitrary error details;
+/// * field-level [validation failures](Violation);
 /// * a reference to MDN documentation regarding
 ///   the HTTP status reported;
 /// * various error tags, which may be convenient
 ///   for internal logging.
 ///
-/// If feature `time` is enabled, also reports
-/// the present timestamp the object was created at.
+/// If feature `time` or `time_crate` is enabled, also
+/// reports the present timestamp the object was created at,
+/// as told by the clock, see `terror::clock`, in the
+/// selected format, see `terror::timestamp::TimestampFormat`.
 ///
 /// If feature `err_id` is enabled, also assigns
-/// a UUID to the error body.
+/// a UUID to the error body; see `terror::id` on how
+/// the UUIDs are generated.
 ///
 /// If feature `infer_http` is enabled, also automatically
 /// sets `short_message` based on the status code, unless
 /// specifically overwritten in the [builder](Builder).
 ///
+/// ### Error chain
+///
+/// Implements [Error], so it may be propagated with `?`
+/// into `Box<dyn Error>` or `anyhow::Error`. If created
+/// via [Terror::from_source] or [Builder::source], also
+/// exposes the original error via [Error::source].
+///
 /// ### Building
 ///
-/// The object _may_ be constructed manually, as
-/// all object fields are `pub`. However, for the
-/// sake of convenience, object construction may
-/// be done via [builder](Builder).
+/// All object fields are `pub`, so that they may
+/// be read and amended. However, the object is
+/// `#[non_exhaustive]`, so that fields may be added
+/// without breaking changes: outside this crate,
+/// it is only constructed via [builder](Builder)
+/// or by deserialisation.
+///
+/// ### Equality
+///
+/// Objects are compared by the data they report;
+/// [Terror::source] does not take part in equality,
+/// so objects created from different sources, or
+/// from none at all, may still be equal.
+///
+/// ### Deserialization
+///
+/// Fields enabled by features may be absent from the
+/// input, see [interoperability](crate#interoperability);
+/// see [strict] for details and for a way to require them.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
-pub struct Terror {
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "utoipa", schema(bound = "D: openapi::UntypedDetails", description = "Error response body"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "D: Details + schemars::JsonSchema", description = "Error response body"))]
+#[serde(try_from = "TerrorRepr<D>", bound(serialize = "D: Details", deserialize = "D: Details"))]
+#[non_exhaustive]
+pub struct Terror<D = HashMap<String, Value>> {
 
     /// HTTP status code
     pub status: u16,
@@ -65,26 +156,62 @@ pub struct Terror {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_code: Option<String>,
 
-    /// Arbitrary error details
-    #[serde(default = "Terror::default_empty_map")]
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    pub details: HashMap<String, Value>,
+    /// Error details; arbitrary JSON values
+    /// by name unless another [Details] type
+    /// is chosen
+    #[serde(skip_serializing_if = "detail::is_empty")]
+    #[cfg_attr(feature = "utoipa", schema(schema_with = openapi::details_schema))]
+    #[cfg_attr(feature = "schemars", schemars(description = "Error details; arbitrary JSON values by name unless another details type is chosen"))]
+    pub details: D,
+
+    /// Field-level validation failures
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<Violation>,
+
+    /// Errors reported by upstream services,
+    /// which caused this one; see [upstream]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "utoipa", schema(no_recursion))]
+    pub upstream: Vec<Terror>,
+
+    /// Errors of individual items of a batch
+    /// operation; see [batch]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ItemError>,
+
+    /// Raw message templates, if kept; see
+    /// [Builder::keep_template]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<Template>,
 
-    /// A reference to the MDN about the status code
+    /// A reference to the MDN about the status code;
+    /// not serialised if empty, see [strict]
     #[cfg(feature = "mdn")]
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub reference: String,
 
-    /// Error timestamp as captured by server
-    #[cfg(feature = "time")]
-    pub timestamp: DateTime<Utc>,
+    /// Error timestamp as captured by server;
+    /// not serialised if at Unix epoch, see [strict]
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    #[serde(serialize_with = "timestamp::serialize", skip_serializing_if = "timestamp::is_epoch")]
+    #[cfg_attr(feature = "utoipa", schema(schema_with = timestamp::openapi_schema))]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "timestamp::json_schema"))]
+    pub timestamp: Timestamp,
 
-    /// Error ID
+    /// Error ID; not serialised if nil,
+    /// see [strict]
     #[cfg(feature = "err_id")]
-    pub id: Uuid
+    #[serde(skip_serializing_if = "Uuid::is_nil")]
+    pub id: Uuid,
+
+    /// Original error; never serialised and
+    /// ignored by equality checks
+    #[serde(skip)]
+    pub source: ErrorSource
 
 }
 
-impl fmt::Display for Terror {
+impl<D> fmt::Display for Terror<D> {
 
     /// Formats the object into a nicely
     /// looking log line. At the minimum,
@@ -101,6 +228,20 @@ impl fmt::Display for Terror {
     }
 }
 
+impl<D: Debug> Error for Terror<D> {
+
+    /// Reports the [source](Terror::source), if any,
+    /// or the first of [upstream](Terror::upstream)
+    /// errors otherwise.
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.get()
+            .map(|err| err as &(dyn Error + 'static))
+            .or_else(|| self.upstream.first()
+                .map(|err| err as &(dyn Error + 'static)))
+    }
+
+}
+
 impl Terror {
 
     /// Constructs a new builder with the
@@ -121,6 +262,7 @@ impl Terror {
     /// let built = Terror::new(429, "some error")
     ///     .build();
     /// ```
+    #[allow(clippy::new_ret_no_self)]
     pub fn new<K: Into<String>>(status: u16, message: K) -> Builder {
         let message: String = message.into();
         #[cfg(feature = "infer_http")]
@@ -136,28 +278,160 @@ impl Terror {
             short_message: shorthand,
             error_code: None,
             details: HashMap::new(),
+            violations: Vec::new(),
+            upstream: Vec::new(),
+            errors: Vec::new(),
+            interpolation: None,
+            keep_template: false,
+            template: None,
 
             #[cfg(feature = "mdn")]
             reference: format!("{}/{}", MDN_STATUS_REF, status),
 
-            #[cfg(feature = "time")]
-            timestamp: Utc::now(),
+            #[cfg(any(feature = "time", feature = "time_crate"))]
+            timestamp: clock::now(),
 
             #[cfg(feature = "err_id")]
-            id: Uuid::new_v4(),
+            id: id::generate(),
+
+            source: ErrorSource::default(),
+        }
+    }
+
+    /// Constructs a new builder of the error declared
+    /// in a [catalog], with its status, message, error
+    /// code and, if declared, short message and
+    /// documentation URL.
+    ///
+    /// The messages are templates: placeholders are
+    /// [interpolated](Builder::interpolate) from the
+    /// details under [MissingKey::Keep], unless another
+    /// policy is set.
+    pub fn from_def(def: &ErrorDef) -> Builder {
+        let mut builder = Terror::new(def.status, def.message)
+            .error_code(def.code)
+            .interpolate(MissingKey::Keep);
+        if let Some(shorthand) = def.shorthand {
+            builder = builder.shorthand(shorthand);
         }
+        if let Some(url) = def.doc_url {
+            builder = builder.add_text_detail(catalog::DOC_URL_DETAIL, url);
+        }
+        builder
     }
 
     /// Constructs a new builder from any
     /// [Error] subtype and assumes HTTP
     /// status of `500 Internal Server Error`.
+    ///
+    /// Only the error message is retained; see
+    /// [Terror::from_source] to keep the error itself.
     pub fn from_error<T: Error>(err: T) -> Builder {
         Terror::new(500, format!("{}", err))
     }
 
-    /// Default handler for JSON map fields.
-    fn default_empty_map() -> HashMap<String, Value> {
-        HashMap::new()
+    /// Constructs a new builder from any
+    /// [Error] subtype and assumes HTTP
+    /// status of `500 Internal Server Error`.
+    ///
+    /// Unlike [Terror::from_error], also retains
+    /// the error itself as a [source](Builder::source).
+    pub fn from_source<T>(err: T) -> Builder
+        where T: Error + Send + Sync + 'static
+    {
+        Terror::new(500, format!("{}", err))
+            .source(err)
+    }
+
+    /// Retrieves the named detail as the requested type.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use terror::{LookupError, Terror};
+    /// let built = Terror::new(429, "too many requests")
+    ///     .add_int_detail("retry_after", 30)
+    ///     .build();
+    ///
+    /// assert_eq!(30, built.detail::<u32>("retry_after").unwrap());
+    /// assert!(matches!(built.detail::<String>("retry_after"), Err(LookupError::Mistyped { .. })));
+    /// assert!(matches!(built.detail::<u32>("limit"), Err(LookupError::Missing { .. })));
+    /// ```
+    pub fn detail<T: DeserializeOwned>(&self, name: &str) -> Result<T, LookupError> {
+        let value = self.details.get(name)
+            .ok_or_else(|| LookupError::Missing { key: String::from(name) })?;
+        T::deserialize(value)
+            .map_err(|cause| LookupError::Mistyped { key: String::from(name), cause })
+    }
+
+    /// Retrieves a value nested in the details
+    /// as the requested type. The [JSON pointer](https://www.rfc-editor.org/rfc/rfc6901)
+    /// is resolved against the details object,
+    /// so that its first segment names the detail;
+    /// the empty pointer refers to the details
+    /// object as a whole.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use serde_json::json;
+    /// use terror::Terror;
+    /// let built = Terror::new(422, "invalid user")
+    ///     .add_value_detail("limits", json!({ "age": [0, 150] }))
+    ///     .build();
+    ///
+    /// assert_eq!(150, built.detail_at::<u8>("/limits/age/1").unwrap());
+    /// ```
+    pub fn detail_at<T: DeserializeOwned>(&self, pointer: &str) -> Result<T, LookupError> {
+        if pointer.is_empty() {
+            let whole = Value::Object(self.details.clone().into_iter().collect());
+            return T::deserialize(whole)
+                .map_err(|cause| LookupError::Mistyped { key: String::from(pointer), cause });
+        }
+        let missing = || LookupError::Missing { key: String::from(pointer) };
+        let path = pointer.strip_prefix('/')
+            .ok_or_else(missing)?;
+        let (name, rest) = match path.find('/') {
+            Some(at) => path.split_at(at),
+            None => (path, "")
+        };
+        let name = name.replace("~1", "/").replace("~0", "~");
+        let value = self.details.get(&name)
+            .and_then(|value| value.pointer(rest))
+            .ok_or_else(missing)?;
+        T::deserialize(value)
+            .map_err(|cause| LookupError::Mistyped { key: String::from(pointer), cause })
+    }
+
+}
+
+impl<D> Terror<D> {
+
+    /// Truncates the chain of [upstream](Terror::upstream)
+    /// errors, so that at most `depth` levels of them
+    /// are kept.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use terror::Terror;
+    /// let root = Terror::new(503, "database unavailable").build();
+    /// let middle = Terror::new(502, "users unavailable")
+    ///     .add_upstream(root)
+    ///     .build();
+    /// let mut top = Terror::new(502, "gateway error")
+    ///     .add_upstream(middle)
+    ///     .build();
+    ///
+    /// top.truncate_upstream(1);
+    /// assert!(top.upstream[0].upstream.is_empty());
+    /// ```
+    pub fn truncate_upstream(&mut self, depth: usize) {
+        match depth {
+            0 => self.upstream.clear(),
+            _ => self.upstream.iter_mut()
+                .for_each(|upstream| upstream.truncate_upstream(depth - 1))
+        }
     }
 
 }
@@ -171,41 +445,323 @@ impl Default for Terror {
 /// A builder for [Terror]. Intended
 /// for one-time use, consumed after
 /// calling [Builder::build].
-pub struct Builder {
+///
+/// Details are added one by one as long as
+/// they are a JSON map, the default; another
+/// [Details] type may be set via [Builder::details].
+pub struct Builder<D = HashMap<String, Value>> {
 
     status: u16,
     message: String,
     short_message: Option<String>,
     error_code: Option<String>,
-    details: HashMap<String, Value>,
+    details: D,
+    violations: Vec<Violation>,
+    upstream: Vec<Terror>,
+    errors: Vec<ItemError>,
+    interpolation: Option<MissingKey>,
+    keep_template: bool,
+    template: Option<Template>,
 
     #[cfg(feature = "mdn")]
     reference: String,
 
-    #[cfg(feature = "time")]
-    timestamp: DateTime<Utc>,
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    timestamp: Timestamp,
 
     #[cfg(feature = "err_id")]
-    id: Uuid
+    id: Uuid,
+
+    source: ErrorSource
 
 }
 
-impl Builder {
-    
+impl<D> Builder<D> {
+
     /// Adds a short error message.
-    pub fn shorthand<K: Into<String>>(mut self, msg: K) -> Builder {
+    pub fn shorthand<K: Into<String>>(mut self, msg: K) -> Builder<D> {
         let into: String = msg.into();
         self.short_message = Some(into);
         self
     }
 
     /// Adds an error code.
-    pub fn error_code<K: Into<String>>(mut self, code: K) -> Builder {
+    pub fn error_code<K: Into<String>>(mut self, code: K) -> Builder<D> {
         let into: String = code.into();
         self.error_code = Some(into);
         self
     }
 
+    /// Replaces the details, possibly with
+    /// another [Details] type.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use serde_derive::{Deserialize, Serialize};
+    /// use terror::Terror;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Quota {
+    ///     limit: u32,
+    ///     retry_after: u32
+    /// }
+    ///
+    /// let built: Terror<Quota> = Terror::new(429, "too many requests")
+    ///     .details(Quota { limit: 100, retry_after: 30 })
+    ///     .build();
+    /// assert_eq!(30, built.details.retry_after);
+    /// ```
+    pub fn details<E>(self, details: E) -> Builder<E> {
+        Builder {
+            status: self.status,
+            message: self.message,
+            short_message: self.short_message,
+            error_code: self.error_code,
+            details,
+            violations: self.violations,
+            upstream: self.upstream,
+            errors: self.errors,
+            interpolation: self.interpolation,
+            keep_template: self.keep_template,
+            template: self.template,
+
+            #[cfg(feature = "mdn")]
+            reference: self.reference,
+
+            #[cfg(any(feature = "time", feature = "time_crate"))]
+            timestamp: self.timestamp,
+
+            #[cfg(feature = "err_id")]
+            id: self.id,
+
+            source: self.source,
+        }
+    }
+
+    /// Adds a field-level validation failure.
+    pub fn add_violation(mut self, violation: Violation) -> Builder<D> {
+        self.violations.push(violation);
+        self
+    }
+
+    /// Adds several field-level validation failures.
+    pub fn add_violations<I>(mut self, violations: I) -> Builder<D>
+        where I: IntoIterator<Item = Violation>
+    {
+        self.violations.extend(violations);
+        self
+    }
+
+    /// Adds an error reported by an upstream
+    /// service, which caused this one.
+    pub fn add_upstream(mut self, upstream: Terror) -> Builder<D> {
+        self.upstream.push(upstream);
+        self
+    }
+
+    /// Adds several errors reported by upstream
+    /// services, which caused this one.
+    pub fn add_upstreams<I>(mut self, upstream: I) -> Builder<D>
+        where I: IntoIterator<Item = Terror>
+    {
+        self.upstream.extend(upstream);
+        self
+    }
+
+    /// Adds an error of an individual
+    /// item of a batch operation.
+    pub fn add_item_error(mut self, error: ItemError) -> Builder<D> {
+        self.errors.push(error);
+        self
+    }
+
+    /// Adds errors of several individual
+    /// items of a batch operation.
+    pub fn add_item_errors<I>(mut self, errors: I) -> Builder<D>
+        where I: IntoIterator<Item = ItemError>
+    {
+        self.errors.extend(errors);
+        self
+    }
+
+    /// Overrides the MDN reference with an
+    /// arbitrary URL.
+    #[cfg(feature = "mdn")]
+    pub fn reference<K: Into<String>>(mut self, url: K) -> Builder<D> {
+        let into: String = url.into();
+        self.reference = into;
+        self
+    }
+
+    /// Overrides the timestamp, which is otherwise
+    /// captured when the builder is created.
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Builder<D> {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Overrides the timestamp with the present
+    /// moment as told by the provided clock.
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    pub fn clock<C>(mut self, clock: &C) -> Builder<D>
+        where C: Clock + ?Sized
+    {
+        self.timestamp = clock.now();
+        self
+    }
+
+    /// Overrides the error ID, which is otherwise
+    /// generated when the builder is created;
+    /// e.g. to report an incoming request ID.
+    #[cfg(feature = "err_id")]
+    pub fn id(mut self, id: Uuid) -> Builder<D> {
+        self.id = id;
+        self
+    }
+
+    /// Overrides the error ID with the one
+    /// produced by the provided generator.
+    #[cfg(feature = "err_id")]
+    pub fn id_generator<G>(mut self, generator: &G) -> Builder<D>
+        where G: IdGenerator + ?Sized
+    {
+        self.id = generator.generate();
+        self
+    }
+
+    /// Attaches the original error, which is
+    /// then reported by [Error::source].
+    pub fn source<E>(mut self, err: E) -> Builder<D>
+        where E: Error + Send + Sync + 'static
+    {
+        self.source = ErrorSource::new(err);
+        self
+    }
+
+    /// Resolves `{name}` placeholders in the message
+    /// and the short message against the details when
+    /// the error is built, with missing details handled
+    /// as per the policy; `{{` and `}}` stand for literal
+    /// braces.
+    ///
+    /// Text details are substituted as they are, others
+    /// as JSON.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use terror::{MissingKey, Terror};
+    /// let built = Terror::new(404, "user {user_id} not found")
+    ///     .add_int_detail("user_id", 42)
+    ///     .interpolate(MissingKey::Keep)
+    ///     .build();
+    /// assert_eq!("user 42 not found", built.message);
+    /// ```
+    pub fn interpolate(mut self, policy: MissingKey) -> Builder<D> {
+        self.interpolation = Some(policy);
+        self
+    }
+
+    /// Keeps the raw templates and the values of the
+    /// resolved placeholders in [Terror::template], so
+    /// that clients may localise messages; has no effect
+    /// unless placeholders are
+    /// [interpolated](Builder::interpolate).
+    pub fn keep_template(mut self) -> Builder<D> {
+        self.keep_template = true;
+        self
+    }
+
+}
+
+impl<D: Serializable> Builder<D> {
+
+    /// Concludes the configuration and produces
+    /// a new [Terror] instance with all
+    /// ownerships transferred, thus fully consuming
+    /// `self`.
+    ///
+    /// Never fails: placeholders, which name no
+    /// detail, are kept as they are under
+    /// [MissingKey::Error]; see [Builder::try_build].
+    pub fn build(self) -> Terror<D> {
+        self.conclude().0
+    }
+
+    /// Same as [Builder::build], but fails if a
+    /// placeholder names no detail under
+    /// [MissingKey::Error].
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use terror::{MissingKey, Terror};
+    /// let failed = Terror::new(404, "user {user_id} not found")
+    ///     .interpolate(MissingKey::Error)
+    ///     .try_build();
+    /// assert_eq!("user_id", failed.unwrap_err().key());
+    /// ```
+    pub fn try_build(self) -> Result<Terror<D>, TemplateError> {
+        match self.conclude() {
+            (built, None) => Ok(built),
+            (_, Some(err)) => Err(err)
+        }
+    }
+
+    fn conclude(self) -> (Terror<D>, Option<TemplateError>) {
+        let (message, short_message, template, failure) = match self.interpolation {
+            Some(policy) => {
+                let details = serde_json::to_value(&self.details)
+                    .unwrap_or(Value::Null);
+                let mut rendering = Rendering::new(&details, policy);
+                let message = rendering.render(&self.message);
+                let short_message = self.short_message.as_deref()
+                    .map(|short| rendering.render(short));
+                let (args, failure) = rendering.finish();
+                let template = match self.keep_template {
+                    true => Some(Template {
+                        message: self.message,
+                        short_message: self.short_message,
+                        args
+                    }),
+                    false => self.template
+                };
+                (message, short_message, template, failure)
+            },
+            None => (self.message, self.short_message, self.template, None)
+        };
+
+        let built = Terror {
+            status: self.status,
+            message,
+            short_message,
+            error_code: self.error_code,
+            details: self.details,
+            violations: self.violations,
+            upstream: self.upstream,
+            errors: self.errors,
+            template,
+
+            #[cfg(feature = "mdn")]
+            reference: self.reference,
+
+            #[cfg(any(feature = "time", feature = "time_crate"))]
+            timestamp: self.timestamp,
+
+            #[cfg(feature = "err_id")]
+            id: self.id,
+
+            source: self.source,
+        };
+        (built, failure)
+    }
+
+}
+
+impl Builder {
+
     /// Adds a text detail.
     pub fn add_text_detail<K, V>(mut self,
                                  name: K,
@@ -256,61 +812,89 @@ impl Builder {
     /// Adds a serialised struct detail from a
     /// provided [Serialize]-annotated object.
     ///
-    /// ### Panics
-    ///
-    /// This method expects that `obj` parameter
-    /// can be correctly serialised into JSON
-    /// and panics if [serde_json::to_value]
-    /// fails.
-    pub fn add_struct_detail<K, S>(mut self,
+    /// Never panics: if serialisation fails, e.g.
+    /// for maps with non-string keys, adds a
+    /// placeholder string instead, same as
+    /// [Builder::add_struct_detail_lenient]. See
+    /// [Builder::try_add_struct_detail] to handle
+    /// the failure instead.
+    pub fn add_struct_detail<K, S>(self,
                                    name: K,
                                    obj: S) -> Builder
         where K: Into<String>,
-              S: Serializable + Debug
+              S: Serializable
     {
-        let into: String = name.into();
-        let value = serde_json::to_value(&obj)
-            .expect(format!("failed to serialise: {:?}", &obj).as_str());
-        self.details.insert(into, value);
-        self
+        self.add_struct_detail_lenient(name, obj)
     }
 
-    /// Concludes the configuration and produces
-    /// a new [Terror] instance with all
-    /// ownerships transferred, thus fully consuming
-    /// `self`.
-    pub fn build(self) -> Terror {
-        Terror {
-            status: self.status,
-            message: self.message.clone(),
-            short_message: self.short_message.clone(),
-            error_code: self.error_code.clone(),
-            details: self.details,
-
-            #[cfg(feature = "mdn")]
-            reference: self.reference.clone(),
-
-            #[cfg(feature = "time")]
-            timestamp: self.timestamp.clone(),
-
-            #[cfg(feature = "err_id")]
-            id: self.id.clone(),
+    /// Adds a serialised struct detail from a
+    /// provided [Serialize]-annotated object,
+    /// or reports a [DetailError] holding the
+    /// builder intact if serialisation fails.
+    pub fn try_add_struct_detail<K, S>(mut self,
+                                       name: K,
+                                       obj: S) -> Result<Builder, DetailError>
+        where K: Into<String>,
+              S: Serializable
+    {
+        let into: String = name.into();
+        match serde_json::to_value(&obj) {
+            Ok(value) => {
+                self.details.insert(into, value);
+                Ok(self)
+            },
+            Err(err) => Err(DetailError::new(into, err, self))
         }
     }
 
+    /// Adds a serialised struct detail from a
+    /// provided [Serialize]-annotated object. If
+    /// serialisation fails, adds a placeholder
+    /// string describing the failure instead.
+    ///
+    /// ### Examples
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    /// use serde_json::json;
+    /// use terror::Terror;
+    ///
+    /// let weights = HashMap::from([((1, 2), 0.5)]);
+    /// let built = Terror::new(500, "generic error")
+    ///     .add_struct_detail_lenient("weights", weights)
+    ///     .build();
+    /// let placeholder = built.details.get("weights")
+    ///     .and_then(|weights| weights.as_str())
+    ///     .unwrap();
+    /// assert!(placeholder.starts_with("<unserialisable: "));
+    /// ```
+    pub fn add_struct_detail_lenient<K, S>(self,
+                                           name: K,
+                                           obj: S) -> Builder
+        where K: Into<String>,
+              S: Serializable
+    {
+        self.try_add_struct_detail(name, obj)
+            .unwrap_or_else(|err| {
+                let (name, cause, builder) = err.into_parts();
+                builder.add_text_detail(name, format!("<unserialisable: {}>", cause))
+            })
+    }
+
 }
 
 #[cfg(feature = "mdn")]
-const MDN_STATUS_REF: &str = "https://developer.mozilla.org/en-US/docs/Web/HTTP/Status";
+pub(crate) const MDN_STATUS_REF: &str = "https://developer.mozilla.org/en-US/docs/Web/HTTP/Status";
 
 #[cfg(test)]
 mod no_feature_test {
+    use std::collections::HashMap;
     use std::error::Error;
     use std::fmt;
     use std::fmt::Formatter;
-    use serde_derive::Serialize;
+    use serde_derive::{Deserialize, Serialize};
     use serde_json::{json, Value};
-    use crate::{Builder, Terror};
+    use crate::{Builder, ErrorSource, LookupError, MissingKey, Terror};
 
     type R = anyhow::Result<()>;
 
@@ -340,6 +924,60 @@ mod no_feature_test {
         compare(expected, actual)
     }
 
+    #[test]
+    fn build_from_source() -> R {
+        let built = Terror::from_source(TestError)
+            .build();
+
+        let source = built.source()
+            .expect("source must be retained");
+        assert_eq!("generic error", source.to_string());
+        assert!(source.is::<TestError>());
+
+        let mut without_source = built.clone();
+        without_source.source = ErrorSource::default();
+        assert_eq!(without_source, built);
+
+        let expected = json!({
+            "status": 500,
+            "message": "generic error"
+        });
+        let actual = serde_json::to_value(built)?;
+        compare(expected, actual)
+    }
+
+    #[test]
+    fn build_w_source() {
+        let built = builder()
+            .source(TestError)
+            .build();
+
+        assert_eq!(404, built.status);
+        assert!(built.clone().source().is_some());
+        assert!(builder().build().source().is_none());
+    }
+
+    #[test]
+    fn propagate_as_error() {
+        fn boxed() -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err(Terror::from_source(TestError).build())?
+        }
+
+        fn wrapped() -> anyhow::Result<()> {
+            Err(Terror::from_source(TestError).build())?
+        }
+
+        let err = boxed().unwrap_err();
+        assert_eq!("(500) :: generic error", err.to_string());
+        assert!(err.downcast_ref::<Terror>().is_some());
+
+        let err = wrapped().unwrap_err();
+        let chain: Vec<String> = err.chain()
+            .map(|err| err.to_string())
+            .collect();
+        assert_eq!(vec!["(500) :: generic error", "generic error"], chain);
+    }
+
     #[test]
     fn build_w_shorthand() -> R {
         let built = builder()
@@ -474,6 +1112,196 @@ mod no_feature_test {
         compare(expected, actual)
     }
 
+    #[test]
+    fn build_w_typed_details() -> R {
+        #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+        struct Quota {
+            limit: u32,
+            retry_after: u32
+        }
+
+        let built: Terror<Quota> = Terror::new(429, "too many requests")
+            .error_code("quota.exceeded")
+            .details(Quota { limit: 100, retry_after: 30 })
+            .build();
+
+        let expected = json!({
+            "status": 429,
+            "message": "too many requests",
+            "error_code": "quota.exceeded",
+            "details": {
+                "limit": 100,
+                "retry_after": 30
+            }
+        });
+        let actual = serde_json::to_value(&built)?;
+        compare(expected, actual.clone())?;
+
+        let as_struct: Terror<Quota> = serde_json::from_value(actual)?;
+        assert_eq!(built, as_struct);
+
+        let invalid = json!({
+            "status": 429,
+            "message": "too many requests",
+            "details": { "limit": "many" }
+        });
+        assert!(serde_json::from_value::<Terror<Quota>>(invalid).is_err());
+
+        let absent = json!({
+            "status": 429,
+            "message": "too many requests"
+        });
+        let err = serde_json::from_value::<Terror<Quota>>(absent).unwrap_err();
+        assert!(err.to_string().contains("details"));
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_absent_typed_details() -> R {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        #[serde(default)]
+        struct Quota {
+            limit: u32
+        }
+
+        impl Default for Quota {
+            fn default() -> Self {
+                Quota { limit: 10 }
+            }
+        }
+
+        let absent = json!({
+            "status": 429,
+            "message": "too many requests"
+        });
+        assert_eq!(10, serde_json::from_value::<Terror<Quota>>(absent.clone())?.details.limit);
+        assert_eq!(None, serde_json::from_value::<Terror<Option<Quota>>>(absent.clone())?.details);
+        assert_eq!((), serde_json::from_value::<Terror<()>>(absent)?.details);
+
+        let built: Terror<Vec<u32>> = Terror::new(429, "too many requests")
+            .details(Vec::new())
+            .build();
+        assert!(serde_json::to_value(&built)?.get("details").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn build_w_optional_typed_details() -> R {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Quota {
+            limit: u32
+        }
+
+        let built: Terror<Option<Quota>> = Terror::new(429, "too many requests")
+            .details(None)
+            .build();
+        let actual = serde_json::to_value(&built)?;
+        assert!(actual.get("details").is_none());
+
+        let as_struct: Terror<Option<Quota>> = serde_json::from_value(actual)?;
+        assert_eq!(None, as_struct.details);
+        Ok(())
+    }
+
+    #[test]
+    fn retrieve_typed_detail() -> R {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Server {
+            id: i32,
+            name: String
+        }
+
+        let inbound = json!({
+            "status": 404,
+            "message": "generic error",
+            "details": {
+                "server": { "id": 25, "name": "server" },
+                "tags": ["a", "b"],
+                "a/b": { "~c": 1 }
+            }
+        });
+        let as_struct: Terror = serde_json::from_value(inbound)?;
+
+        assert_eq!(Server { id: 25, name: String::from("server") }, as_struct.detail("server")?);
+        assert_eq!(vec![String::from("a"), String::from("b")], as_struct.detail::<Vec<String>>("tags")?);
+        assert_eq!(25, as_struct.detail_at::<i32>("/server/id")?);
+        assert_eq!("b", as_struct.detail_at::<String>("/tags/1")?);
+        assert_eq!(1, as_struct.detail_at::<u8>("/a~1b/~0c")?);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Whole {
+            server: Server,
+            tags: Vec<String>
+        }
+        let whole = as_struct.detail_at::<Whole>("")?;
+        assert_eq!(25, whole.server.id);
+        assert_eq!(2, whole.tags.len());
+        assert_eq!(as_struct.details.len(), as_struct.detail_at::<HashMap<String, Value>>("")?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn retrieve_missing_or_mistyped_detail() {
+        let built = builder()
+            .add_int_detail("id", 25)
+            .add_value_detail("tags", json!(["a", "b"]))
+            .build();
+
+        let missing = built.detail::<i32>("name").unwrap_err();
+        assert!(matches!(missing, LookupError::Missing { .. }));
+        assert_eq!("missing detail 'name'", missing.to_string());
+        assert!(missing.source().is_none());
+
+        let mistyped = built.detail::<String>("id").unwrap_err();
+        assert!(matches!(mistyped, LookupError::Mistyped { .. }));
+        assert_eq!("id", mistyped.key());
+        assert!(mistyped.source().is_some());
+
+        assert!(matches!(built.detail_at::<String>("/tags/2"), Err(LookupError::Missing { .. })));
+        assert!(matches!(built.detail_at::<String>("tags"), Err(LookupError::Missing { .. })));
+        assert!(matches!(built.detail_at::<i32>("/tags/0"), Err(LookupError::Mistyped { .. })));
+        assert!(matches!(built.detail_at::<i32>(""), Err(LookupError::Mistyped { .. })));
+    }
+
+    #[test]
+    fn build_w_fallible_struct_detail() -> R {
+        let built = builder()
+            .try_add_struct_detail("key", vec![1, 2])?
+            .build();
+        assert_eq!(Some(&json!([1, 2])), built.details.get("key"));
+
+        let err = builder()
+            .add_bool_detail("flg", true)
+            .try_add_struct_detail("key", HashMap::from([((1, 2), 0.5)]))
+            .err()
+            .unwrap();
+        assert_eq!("key", err.name());
+        assert!(err.to_string().starts_with("failed to serialise detail 'key': "));
+        assert!(err.source().is_some());
+
+        let built = err.into_builder().build();
+        assert_eq!(Some(&json!(true)), built.details.get("flg"));
+        assert!(!built.details.contains_key("key"));
+        Ok(())
+    }
+
+    #[test]
+    fn build_w_lenient_struct_detail() {
+        let built = builder()
+            .add_struct_detail_lenient("key", HashMap::from([((1, 2), 0.5)]))
+            .add_struct_detail("same", HashMap::from([((1, 2), 0.5)]))
+            .add_struct_detail_lenient("other", vec![1, 2])
+            .build();
+
+        let placeholder = built.details.get("key")
+            .and_then(Value::as_str)
+            .unwrap();
+        assert!(placeholder.starts_with("<unserialisable: "));
+        assert!(placeholder.ends_with('>'));
+        assert_eq!(built.details.get("key"), built.details.get("same"));
+        assert_eq!(Some(&json!([1, 2])), built.details.get("other"));
+    }
+
     #[test]
     fn build_w_list_struct_detail() -> R {
         #[derive(Serialize, Debug)]
@@ -536,6 +1364,7 @@ mod no_feature_test {
     #[test]
     #[cfg(not(feature = "err_id"))]
     #[cfg(not(feature = "time"))]
+    #[cfg(not(feature = "time_crate"))]
     #[cfg(not(feature = "mdn"))]
     fn deserialize_all_fields() {
         let inbound = json!({
@@ -576,6 +1405,7 @@ mod no_feature_test {
     #[test]
     #[cfg(not(feature = "err_id"))]
     #[cfg(not(feature = "time"))]
+    #[cfg(not(feature = "time_crate"))]
     #[cfg(not(feature = "mdn"))]
     fn deserialize_some_fields() {
         let inbound = json!({
@@ -608,9 +1438,10 @@ mod no_feature_test {
 
     impl Error for TestError {}
 
+    #[allow(unused_mut)]
     fn compare(expected: Value, mut actual: Value) -> R {
 
-        #[cfg(feature = "time")]
+        #[cfg(any(feature = "time", feature = "time_crate"))]
         actual.as_object_mut().unwrap().remove("timestamp");
 
         #[cfg(feature = "err_id")]
@@ -626,9 +1457,101 @@ mod no_feature_test {
         Ok(())
     }
 
+    #[test]
+    fn build_w_interpolated_message() {
+        let built = Terror::new(404, "user {user_id} not found")
+            .shorthand("{kind} missing")
+            .add_int_detail("user_id", 42)
+            .add_text_detail("kind", "User")
+            .interpolate(MissingKey::Keep)
+            .build();
+
+        assert_eq!("user 42 not found", built.message);
+        assert_eq!(Some(String::from("User missing")), built.short_message);
+        assert_eq!(Some(&json!(42)), built.details.get("user_id"));
+        assert_eq!(None, built.template);
+    }
+
+    #[test]
+    fn build_wo_interpolation() {
+        let built = Terror::new(400, "expected {user_id}")
+            .add_int_detail("user_id", 42)
+            .build();
+
+        assert_eq!("expected {user_id}", built.message);
+    }
+
+    #[test]
+    fn build_w_missing_placeholder() {
+        let template = || Terror::new(404, "user {user_id} not found");
+
+        assert_eq!(
+            "user {user_id} not found",
+            template().interpolate(MissingKey::Keep).build().message
+        );
+        assert_eq!(
+            "user  not found",
+            template().interpolate(MissingKey::Blank).build().message
+        );
+
+        assert_eq!(
+            "user  not found",
+            template().interpolate(MissingKey::Blank).try_build().unwrap().message
+        );
+        assert_eq!(
+            "user {user_id} not found",
+            template().interpolate(MissingKey::Error).build().message
+        );
+
+        let err = template().interpolate(MissingKey::Error).try_build().unwrap_err();
+        assert_eq!("user_id", err.key());
+        assert_eq!("no detail for placeholder 'user_id'", err.to_string());
+        assert!(template().try_build().is_ok());
+    }
+
+    #[test]
+    fn build_w_kept_template() -> R {
+        let built = Terror::new(404, "user {user_id} not found")
+            .add_int_detail("user_id", 42)
+            .add_text_detail("tenant", "acme")
+            .interpolate(MissingKey::Keep)
+            .keep_template()
+            .build();
+
+        let template = built.template.clone().unwrap();
+        assert_eq!("user {user_id} not found", template.message);
+        assert_eq!(built.short_message, template.short_message);
+        assert_eq!(HashMap::from([(String::from("user_id"), json!(42))]), template.args);
+
+        let actual = serde_json::to_value(&built)?;
+        assert_eq!(json!("user {user_id} not found"), actual["template"]["message"]);
+        assert_eq!(json!({ "user_id": 42 }), actual["template"]["args"]);
+
+        let as_struct: Terror = serde_json::from_value(actual)?;
+        assert_eq!(built.template, as_struct.template);
+        Ok(())
+    }
+
+    #[test]
+    fn build_typed_details_w_interpolation() -> R {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Quota {
+            limit: u32
+        }
+
+        let built = Terror::new(429, "limit of {limit} exceeded")
+            .details(Quota { limit: 100 })
+            .interpolate(MissingKey::Error)
+            .try_build()?;
+
+        assert_eq!("limit of 100 exceeded", built.message);
+        Ok(())
+    }
+
+    #[allow(unused_mut)]
     fn compare_respecting_manually_set_fields(expected: Value, mut actual: Value) -> R {
 
-        #[cfg(feature = "time")]
+        #[cfg(any(feature = "time", feature = "time_crate"))]
         actual.as_object_mut().unwrap().remove("timestamp");
 
         #[cfg(feature = "err_id")]
@@ -662,7 +1585,7 @@ mod with_features_test {
 
     #[test]
     fn build_w_explicit_status() -> R {
-        let mut built = builder().build();
+        let built = builder().build();
 
         let now = Utc::now();
 
@@ -672,10 +1595,12 @@ mod with_features_test {
             built.timestamp.date_naive()
         );
 
-        // overwrite to check json values
+        // override to check json values
         let uuid = Uuid::new_v4();
-        built.id = uuid;
-        built.timestamp = now;
+        let built = builder()
+            .id(uuid)
+            .timestamp(now)
+            .build();
 
         let expected = json!({
             "status": 404,
@@ -692,7 +1617,7 @@ mod with_features_test {
     #[test]
     fn build_from_error() -> R {
         let error = TestError;
-        let mut built = Terror::from_error(error)
+        let built = Terror::from_error(error)
             .build();
 
         let now = Utc::now();
@@ -703,10 +1628,12 @@ mod with_features_test {
             built.timestamp.date_naive()
         );
 
-        // overwrite to check json values
+        // override to check json values
         let uuid = Uuid::new_v4();
-        built.id = uuid;
-        built.timestamp = now;
+        let built = Terror::from_error(TestError)
+            .id(uuid)
+            .timestamp(now)
+            .build();
 
         let expected = json!({
             "status": 500,
@@ -722,13 +1649,13 @@ mod with_features_test {
 
     #[test]
     fn build_w_reference() -> R {
-        let mut built = builder().build();
-
-        // overwrite to check json values
+        // override to check json values
         let now = Utc::now();
         let uuid = Uuid::new_v4();
-        built.id = uuid;
-        built.timestamp = now;
+        let built = builder()
+            .id(uuid)
+            .timestamp(now)
+            .build();
 
         let expected = json!({
             "status": 404,
@@ -742,6 +1669,29 @@ mod with_features_test {
         compare(expected, actual)
     }
 
+    #[test]
+    fn build_w_overridden_fields() -> R {
+        let uuid = Uuid::from_str("2d10a950-d6f4-11ec-ab97-00155d887325")?;
+        let timestamp = DateTime::parse_from_rfc3339("2022-01-01T21:00:00Z")?
+            .with_timezone(&Utc);
+        let built = builder()
+            .id(uuid)
+            .timestamp(timestamp)
+            .reference("https://example.com/errors/404")
+            .build();
+
+        let expected = json!({
+            "status": 404,
+            "message": "generic error",
+            "id": "2d10a950-d6f4-11ec-ab97-00155d887325",
+            "timestamp": "2022-01-01T21:00:00Z",
+            "reference": "https://example.com/errors/404",
+            "short_message": "404 Not Found"
+        });
+        let actual = serde_json::to_value(built)?;
+        compare(expected, actual)
+    }
+
     #[test]
     fn deserialize_some_fields() {
         let inbound = json!({
@@ -756,19 +1706,17 @@ mod with_features_test {
         let as_struct = serde_json::from_value(inbound);
         assert!(as_struct.is_ok());
 
-        let mut expected = Terror::new(405,"Method not allowed; use GET")
+        let expected = Terror::new(405,"Method not allowed; use GET")
+            .id(Uuid::from_str("2d10a950-d6f4-11ec-ab97-00155d887325").unwrap())
+            .timestamp(DateTime::from_naive_utc_and_offset(
+                NaiveDateTime::new(
+                    NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
+                    NaiveTime::from_hms_opt(21, 0, 0).unwrap()
+                ),
+                Utc
+            ))
             .build();
 
-        expected.id = Uuid::from_str("2d10a950-d6f4-11ec-ab97-00155d887325")
-            .unwrap();
-        expected.timestamp = DateTime::from_naive_utc_and_offset(
-            NaiveDateTime::new(
-                NaiveDate::from_ymd_opt(2022, 1, 1).unwrap(),
-                NaiveTime::from_hms_opt(21, 0, 0).unwrap()
-            ),
-            Utc
-        );
-
         assert_eq!(expected, as_struct.unwrap());
     }
 
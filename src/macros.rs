@@ -0,0 +1,133 @@
+/// Builds a [Terror](crate::Terror) in one go.
+///
+/// Accepts the status and the message as a format
+/// string with arguments, optionally followed by a
+/// semicolon and comma-separated fields:
+/// * `code = ...` - the [error code](crate::Builder::error_code);
+/// * `short = ...` - the [short message](crate::Builder::shorthand);
+/// * `key = ...` or `"key" = ...` - a detail, serialised
+///   into JSON through [Serialize](serde::Serialize); use
+///   the quoted form for details named `code` or `short`,
+///   or not valid as identifiers.
+///
+/// Never panics: details, which fail to serialise, are
+/// added as [placeholders](crate::Builder::add_struct_detail_lenient).
+///
+/// ### Examples
+///
+/// ```rust
+/// use terror::terror;
+///
+/// let user = 42;
+/// let built = terror!(404, "no such user: {}", user;
+///     code = "user.missing",
+///     short = "Not found",
+///     user = user,
+///     "retry-after" = 30
+/// );
+/// assert_eq!("no such user: 42", built.message);
+/// assert_eq!(Some(String::from("user.missing")), built.error_code);
+/// assert_eq!(Some(&serde_json::json!(42)), built.details.get("user"));
+/// ```
+#[macro_export]
+macro_rules! terror {
+    ($status:expr, $fmt:literal $(, $arg:expr)* $(,)? $(; $($field:tt)*)?) => {{
+        let builder = $crate::Terror::new($status, ::std::format!($fmt $(, $arg)*));
+        $( let builder = $crate::__terror_fields!(builder; $($field)*); )?
+        builder.build()
+    }};
+}
+
+/// Applies the fields of [terror!] one by one.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __terror_fields {
+    ($builder:expr;) => {
+        $builder
+    };
+    ($builder:expr; code = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__terror_fields!($builder.error_code($value); $($($rest)*)?)
+    };
+    ($builder:expr; short = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__terror_fields!($builder.shorthand($value); $($($rest)*)?)
+    };
+    ($builder:expr; $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__terror_fields!(
+            $builder.add_struct_detail_lenient(::std::stringify!($key), $value);
+            $($($rest)*)?
+        )
+    };
+    ($builder:expr; $key:literal = $value:expr $(, $($rest:tt)*)?) => {
+        $crate::__terror_fields!($builder.add_struct_detail_lenient($key, $value); $($($rest)*)?)
+    };
+}
+
+#[cfg(test)]
+mod macros_test {
+    use serde_derive::Serialize;
+    use serde_json::json;
+    use crate::Terror;
+
+    #[test]
+    fn message_only() {
+        let built = terror!(404, "no such user");
+
+        assert_eq!(404, built.status);
+        assert_eq!("no such user", built.message);
+        assert!(built.details.is_empty());
+    }
+
+    #[test]
+    fn formatted_message() {
+        let user = "alice";
+        let id = 42;
+
+        assert_eq!("no such user: 42", terror!(404, "no such user: {}", id).message);
+        assert_eq!("no such user: alice", terror!(404, "no such user: {user}").message);
+        assert_eq!("alice#42", terror!(404, "{}#{}", user, id,).message);
+    }
+
+    #[test]
+    fn all_fields() {
+        #[derive(Debug, Serialize)]
+        struct Range {
+            min: u8,
+            max: u8
+        }
+
+        let built = terror!(422, "invalid age: {}", -1;
+            code = "user.invalid",
+            short = "Invalid",
+            age = -1,
+            range = Range { min: 0, max: 150 },
+            "code" = "AGE",
+            "retry-after" = 30,
+        );
+
+        let expected = Terror::new(422, "invalid age: -1")
+            .error_code("user.invalid")
+            .shorthand("Invalid")
+            .add_value_detail("age", json!(-1))
+            .add_value_detail("range", json!({ "min": 0, "max": 150 }))
+            .add_value_detail("code", json!("AGE"))
+            .add_value_detail("retry-after", json!(30))
+            .build();
+        assert_eq!(expected.message, built.message);
+        assert_eq!(expected.error_code, built.error_code);
+        assert_eq!(expected.short_message, built.short_message);
+        assert_eq!(expected.details, built.details);
+    }
+
+    #[test]
+    fn unserialisable_detail() {
+        use std::collections::HashMap;
+
+        let built = terror!(500, "generic error"; weights = HashMap::from([((1, 2), 0.5)]));
+
+        let placeholder = built.details.get("weights")
+            .and_then(|weights| weights.as_str())
+            .unwrap();
+        assert!(placeholder.starts_with("<unserialisable: "));
+    }
+
+}
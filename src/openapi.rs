@@ -0,0 +1,384 @@
+//! OpenAPI documentation of error responses.
+//!
+//! With feature `utoipa` enabled, [Terror] implements
+//! [ToSchema], so that it may be referenced by
+//! `#[utoipa::path]` responses. Fields enabled by
+//! features, such as `id` or `timestamp`, are only
+//! present in the schema if the features are enabled.
+//!
+//! The schema of [Terror] documents its default details
+//! as an arbitrary JSON object. Errors with another
+//! [Details](crate::Details) type are documented by
+//! [TypedTerror] instead, e.g. `TypedTerror<Quota>`,
+//! which refers to the schema of the details type.
+//!
+//! Declaring the same error responses on every path
+//! is tedious; [StandardErrors] adds them to all the
+//! operations of a document at once.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::openapi::StandardErrors;
+//! use utoipa::openapi::{OpenApi, OpenApiBuilder};
+//! use utoipa::Modify;
+//!
+//! let mut doc: OpenApi = OpenApiBuilder::new().build();
+//! StandardErrors::new([400, 404, 500]).modify(&mut doc);
+//! assert!(doc.components.unwrap().schemas.contains_key("Terror"));
+//! ```
+//!
+//! With typed details:
+//!
+//! ```rust
+//! use terror::openapi::StandardErrors;
+//! use utoipa::openapi::{OpenApi, OpenApiBuilder};
+//! use utoipa::{Modify, ToSchema};
+//!
+//! #[derive(ToSchema)]
+//! struct Quota {
+//!     limit: u32
+//! }
+//!
+//! let mut doc: OpenApi = OpenApiBuilder::new().build();
+//! StandardErrors::new([429]).details::<Quota>().modify(&mut doc);
+//! let schemas = doc.components.unwrap().schemas;
+//! assert!(schemas.contains_key("Terror_Quota"));
+//! assert!(schemas.contains_key("Quota"));
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::marker::PhantomData;
+
+use serde_json::Value;
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::schema::{Object, ObjectBuilder, SchemaType, Type};
+use utoipa::openapi::{ContentBuilder, OpenApi, Ref, RefOr, Response, ResponseBuilder, Schema};
+use utoipa::{Modify, PartialSchema, ToSchema};
+
+use crate::Terror;
+
+/// Content type of error responses.
+const CONTENT_TYPE: &str = "application/json";
+
+/// Name of the details property.
+const DETAILS: &str = "details";
+
+/// Details documented by the schema of [Terror]
+/// itself, i.e. the default ones; any other type
+/// is documented by [TypedTerror].
+pub trait UntypedDetails: sealed::Sealed {}
+
+impl UntypedDetails for HashMap<String, Value> {}
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for std::collections::HashMap<String, serde_json::Value> {}
+}
+
+/// Describes the default details as a nullable object;
+/// the description is spelled out, as the doc comment
+/// of the field is meant for rustdoc.
+pub(crate) fn details_schema() -> Object {
+    ObjectBuilder::new()
+        .schema_type(SchemaType::from_iter([Type::Object, Type::Null]))
+        .description(Some("Error details; arbitrary JSON values by name unless another details type is chosen"))
+        .build()
+}
+
+/// Schema of [Terror] with details of type `D`, named
+/// after it, e.g. `Terror_Quota`; meant to be referred
+/// to by `#[utoipa::path]` responses, never constructed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TypedTerror<D>(PhantomData<fn() -> D>);
+
+impl<D: ToSchema> PartialSchema for TypedTerror<D> {
+    fn schema() -> RefOr<Schema> {
+        let mut schema = <Terror as PartialSchema>::schema();
+        if let RefOr::T(Schema::Object(object)) = &mut schema {
+            object.properties.insert(
+                String::from(DETAILS),
+                Ref::from_schema_name(D::name()).into()
+            );
+        }
+        schema
+    }
+}
+
+impl<D: ToSchema> ToSchema for TypedTerror<D> {
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("{}_{}", <Terror as ToSchema>::name(), D::name()))
+    }
+
+    /// Upstream errors refer to the [Terror] schema,
+    /// so it is collected along with that of `D`.
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.push((String::from(<Terror as ToSchema>::name()), <Terror as PartialSchema>::schema()));
+        <Terror as ToSchema>::schemas(schemas);
+        schemas.push((String::from(D::name()), D::schema()));
+        D::schemas(schemas);
+    }
+}
+
+/// Constructs the documentation of an error response
+/// with the provided status, referring to the [Terror]
+/// schema.
+pub fn error_response(status: u16) -> Response {
+    response_of::<Terror>(status)
+}
+
+/// Constructs the documentation of an error response
+/// with the provided status, referring to the schema `S`.
+fn response_of<S: ToSchema>(status: u16) -> Response {
+    let content = ContentBuilder::new()
+        .schema(Some(Ref::from_schema_name(S::name())))
+        .build();
+    ResponseBuilder::new()
+        .description(description_of(status))
+        .content(CONTENT_TYPE, content)
+        .build()
+}
+
+/// Constructs the documentation of error responses with
+/// the provided statuses, keyed as `#[utoipa::path]` and
+/// [IntoResponses](utoipa::IntoResponses) expect.
+pub fn error_responses<I>(statuses: I) -> BTreeMap<String, RefOr<Response>>
+    where I: IntoIterator<Item = u16>
+{
+    statuses.into_iter()
+        .map(|status| (status.to_string(), RefOr::T(error_response(status))))
+        .collect()
+}
+
+/// A [Modify] adding error responses with the provided
+/// statuses to every operation of an OpenAPI document,
+/// along with their schema `S`: [Terror] by default or
+/// [TypedTerror], see [StandardErrors::details].
+///
+/// Responses, which an operation already declares for
+/// the same status, are kept as they are.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StandardErrors<S = Terror> {
+    statuses: Vec<u16>,
+    schema: PhantomData<fn() -> S>
+}
+
+impl StandardErrors {
+
+    /// Constructs a modifier adding error
+    /// responses with the provided statuses.
+    pub fn new<I: IntoIterator<Item = u16>>(statuses: I) -> StandardErrors {
+        StandardErrors {
+            statuses: statuses.into_iter().collect(),
+            schema: PhantomData
+        }
+    }
+
+}
+
+impl<S: ToSchema> StandardErrors<S> {
+
+    /// Documents the responses as errors with
+    /// details of type `D`, see [TypedTerror].
+    pub fn details<D: ToSchema>(self) -> StandardErrors<TypedTerror<D>> {
+        StandardErrors {
+            statuses: self.statuses,
+            schema: PhantomData
+        }
+    }
+
+    fn add_to(&self, operation: &mut Operation) {
+        for status in &self.statuses {
+            operation.responses.responses
+                .entry(status.to_string())
+                .or_insert_with(|| RefOr::T(response_of::<S>(*status)));
+        }
+    }
+
+}
+
+impl<S: ToSchema> Modify for StandardErrors<S> {
+    fn modify(&self, openapi: &mut OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get, &mut item.put, &mut item.post, &mut item.delete,
+                &mut item.options, &mut item.head, &mut item.patch, &mut item.trace
+            ];
+            for operation in operations.into_iter().flatten() {
+                self.add_to(operation);
+            }
+        }
+
+        let mut schemas = vec![(String::from(S::name()), S::schema())];
+        S::schemas(&mut schemas);
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        for (name, schema) in schemas {
+            components.schemas.entry(name).or_insert(schema);
+        }
+    }
+}
+
+/// Describes the status as its reason phrase.
+fn description_of(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        402 => "Payment Required",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        406 => "Not Acceptable",
+        408 => "Request Timeout",
+        409 => "Conflict",
+        410 => "Gone",
+        412 => "Precondition Failed",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Content",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        501 => "Not Implemented",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => match status / 100 {
+            4 => "Client Error",
+            5 => "Server Error",
+            _ => "Error"
+        }
+    }
+}
+
+#[cfg(test)]
+mod openapi_test {
+    use serde_json::{json, Value};
+    use utoipa::openapi::path::{HttpMethod, OperationBuilder, PathItem};
+    use utoipa::openapi::{OpenApi, OpenApiBuilder, PathsBuilder, ResponseBuilder};
+    use utoipa::{Modify, PartialSchema, ToSchema};
+    use crate::openapi::{error_responses, StandardErrors, TypedTerror};
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    fn properties() -> anyhow::Result<Value> {
+        let schema = serde_json::to_value(<Terror as PartialSchema>::schema())?;
+        Ok(schema["properties"].clone())
+    }
+
+    #[test]
+    fn schema_of_terror() -> R {
+        let properties = properties()?;
+
+        assert_eq!(json!("integer"), properties["status"]["type"]);
+        assert_eq!(json!("string"), properties["message"]["type"]);
+        assert_eq!(
+            json!("#/components/schemas/Violation"),
+            properties["violations"]["items"]["$ref"]
+        );
+        assert_eq!(
+            json!("#/components/schemas/Terror"),
+            properties["upstream"]["items"]["$ref"]
+        );
+        assert_eq!(
+            json!("Error details; arbitrary JSON values by name unless another details type is chosen"),
+            properties["details"]["description"]
+        );
+        assert!(properties.get("source").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn schema_of_feature_fields() -> R {
+        let properties = properties()?;
+
+        assert_eq!(cfg!(feature = "err_id"), properties.get("id").is_some());
+        assert_eq!(cfg!(feature = "mdn"), properties.get("reference").is_some());
+        assert_eq!(
+            cfg!(any(feature = "time", feature = "time_crate")),
+            properties.get("timestamp").is_some()
+        );
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        assert_eq!(
+            json!({ "type": "string", "format": "date-time" }),
+            properties["timestamp"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn schema_of_typed_details() -> R {
+        #[derive(ToSchema)]
+        #[allow(dead_code)]
+        struct Quota {
+            limit: u32
+        }
+
+        assert_eq!("Terror_Quota", <TypedTerror<Quota> as ToSchema>::name());
+        let schema = serde_json::to_value(<TypedTerror<Quota> as PartialSchema>::schema())?;
+        assert_eq!(
+            json!("#/components/schemas/Quota"),
+            schema["properties"]["details"]["$ref"]
+        );
+        assert_eq!(
+            json!("#/components/schemas/Terror"),
+            schema["properties"]["upstream"]["items"]["$ref"]
+        );
+
+        let operation = OperationBuilder::new().build();
+        let mut doc: OpenApi = OpenApiBuilder::new()
+            .paths(PathsBuilder::new().path("/quotas", PathItem::new(HttpMethod::Post, operation)))
+            .build();
+        StandardErrors::new([429]).details::<Quota>().modify(&mut doc);
+
+        let actual = serde_json::to_value(&doc)?;
+        assert_eq!(
+            json!("#/components/schemas/Terror_Quota"),
+            actual["paths"]["/quotas"]["post"]["responses"]["429"]["content"]["application/json"]["schema"]["$ref"]
+        );
+        for name in ["Terror_Quota", "Terror", "Quota", "Violation"] {
+            assert!(actual["components"]["schemas"].get(name).is_some(), "missing schema {}", name);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn responses_by_status() -> R {
+        let responses = serde_json::to_value(error_responses([404, 599]))?;
+
+        assert_eq!(json!("Not Found"), responses["404"]["description"]);
+        assert_eq!(json!("Server Error"), responses["599"]["description"]);
+        assert_eq!(
+            json!("#/components/schemas/Terror"),
+            responses["404"]["content"]["application/json"]["schema"]["$ref"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn add_standard_errors() -> R {
+        let operation = OperationBuilder::new()
+            .response("200", ResponseBuilder::new().description("OK").build())
+            .response("404", ResponseBuilder::new().description("No such user").build())
+            .build();
+        let mut doc: OpenApi = OpenApiBuilder::new()
+            .paths(PathsBuilder::new().path("/users/{id}", PathItem::new(HttpMethod::Get, operation)))
+            .build();
+
+        StandardErrors::new([404, 500]).modify(&mut doc);
+
+        let actual = serde_json::to_value(&doc)?;
+        let responses = &actual["paths"]["/users/{id}"]["get"]["responses"];
+        assert_eq!(json!("OK"), responses["200"]["description"]);
+        assert_eq!(json!("No such user"), responses["404"]["description"]);
+        assert_eq!(json!("Internal Server Error"), responses["500"]["description"]);
+
+        let schemas = &actual["components"]["schemas"];
+        for name in ["Terror", "Violation", "ItemError", "ItemKey"] {
+            assert!(schemas.get(name).is_some(), "missing schema {}", name);
+        }
+        Ok(())
+    }
+
+}
@@ -0,0 +1,458 @@
+use std::collections::HashMap;
+use serde::Serialize as Serializable;
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+
+#[cfg(any(feature = "time", feature = "time_crate"))]
+use crate::timestamp;
+#[cfg(feature = "err_id")]
+use uuid::Uuid;
+
+use crate::batch::ItemError;
+use crate::{Template, Terror, Violation};
+
+/// Media type of [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457)
+/// problem details documents.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Default problem type as per RFC 9457.
+const ABOUT_BLANK: &str = "about:blank";
+
+/// Extension member carrying [Terror::error_code].
+const ERROR_CODE_MEMBER: &str = "error_code";
+
+/// Extension member carrying [Terror::violations].
+const VIOLATIONS_MEMBER: &str = "violations";
+
+/// Extension member carrying [Terror::upstream].
+const UPSTREAM_MEMBER: &str = "upstream";
+
+/// Extension member carrying [Terror::errors].
+const ERRORS_MEMBER: &str = "errors";
+
+/// Extension member carrying [Terror::template].
+const TEMPLATE_MEMBER: &str = "template";
+
+/// Extension member carrying the timestamp.
+const TIMESTAMP_MEMBER: &str = "timestamp";
+
+/// Extension member carrying details, which would
+/// otherwise collide with another member.
+const DETAILS_MEMBER: &str = "details";
+
+/// Prefix of the `instance` URI carrying the error ID.
+#[cfg(feature = "err_id")]
+const UUID_URN_PREFIX: &str = "urn:uuid:";
+
+/// Standard members, which extension members may not shadow.
+const RESERVED_MEMBERS: [&str; 5] = ["type", "title", "status", "detail", "instance"];
+
+/// Extension members, which details may not shadow.
+const EXTENSION_MEMBERS: [&str; 7] = [
+    ERROR_CODE_MEMBER, VIOLATIONS_MEMBER, UPSTREAM_MEMBER, ERRORS_MEMBER,
+    TEMPLATE_MEMBER, TIMESTAMP_MEMBER, DETAILS_MEMBER
+];
+
+/// An [RFC 9457](https://www.rfc-editor.org/rfc/rfc9457)
+/// problem details object, i.e. the body of an
+/// `application/problem+json` response.
+///
+/// ### Mapping
+///
+/// Converts from and into [Terror] as follows:
+/// * `type` - `Terror::reference` if feature `mdn` is
+///   enabled and it is not empty, `about:blank` otherwise;
+/// * `title` - [Terror::short_message];
+/// * `status` - [Terror::status];
+/// * `detail` - [Terror::message];
+/// * `instance` - `urn:uuid:` URN of `Terror::id` if
+///   feature `err_id` is enabled and it is not nil,
+///   absent otherwise.
+///
+/// [Terror::details] are flattened into extension
+/// members, along with `error_code`, `violations`,
+/// `upstream`, `errors`, `template` and, if feature
+/// `time` is enabled, `timestamp`. Details named after
+/// any of these or a standard member are nested in the
+/// `details` extension member instead.
+///
+/// Members, which do not map onto a [Terror] field,
+/// such as a foreign `instance` or a `timestamp` in an
+/// unknown format, are kept as details under their
+/// own names.
+///
+/// ### Examples
+///
+/// ```rust
+/// use terror::{Problem, Terror};
+/// let problem: Problem = Terror::new(404, "no such user")
+///     .error_code("user.missing")
+///     .build()
+///     .into();
+/// let back: Terror = problem.into();
+/// assert_eq!(Some(String::from("user.missing")), back.error_code);
+/// ```
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct Problem {
+
+    /// URI reference identifying the problem type
+    #[serde(rename = "type", default = "Problem::default_type")]
+    pub problem_type: String,
+
+    /// Short summary of the problem type; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// HTTP status code; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Explanation specific to this occurrence; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// URI reference identifying this occurrence; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Extension members
+    #[serde(flatten)]
+    pub extensions: HashMap<String, Value>
+
+}
+
+impl Problem {
+
+    /// Default handler for `type` member.
+    fn default_type() -> String {
+        String::from(ABOUT_BLANK)
+    }
+
+    /// Checks if the name belongs to a standard
+    /// or an extension member.
+    fn is_member(name: &str) -> bool {
+        RESERVED_MEMBERS.contains(&name) || EXTENSION_MEMBERS.contains(&name)
+    }
+
+}
+
+/// Serialises a [Terror] field into an extension member.
+///
+/// Cannot fail: the fields only nest plain values and
+/// maps with string keys, which always fit into JSON.
+fn member<T: Serializable>(field: &T) -> Value {
+    serde_json::to_value(field)
+        .expect("terror fields always serialise into JSON")
+}
+
+impl From<Terror> for Problem {
+    fn from(terror: Terror) -> Self {
+        let (colliding, mut extensions): (HashMap<String, Value>, HashMap<String, Value>) =
+            terror.details.into_iter()
+                .partition(|(key, _)| Problem::is_member(key));
+        if !colliding.is_empty() {
+            extensions.insert(String::from(DETAILS_MEMBER), Value::Object(colliding.into_iter().collect()));
+        }
+        if let Some(code) = terror.error_code {
+            extensions.insert(String::from(ERROR_CODE_MEMBER), Value::String(code));
+        }
+        if !terror.violations.is_empty() {
+            extensions.insert(String::from(VIOLATIONS_MEMBER), member(&terror.violations));
+        }
+        if !terror.upstream.is_empty() {
+            extensions.insert(String::from(UPSTREAM_MEMBER), member(&terror.upstream));
+        }
+        if !terror.errors.is_empty() {
+            extensions.insert(String::from(ERRORS_MEMBER), member(&terror.errors));
+        }
+        if let Some(template) = &terror.template {
+            extensions.insert(String::from(TEMPLATE_MEMBER), member(template));
+        }
+
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        if !timestamp::is_epoch(&terror.timestamp) {
+            extensions.insert(
+                String::from(TIMESTAMP_MEMBER),
+                timestamp::to_value(&terror.timestamp)
+            );
+        }
+
+        #[cfg(feature = "mdn")]
+        let problem_type = match terror.reference.is_empty() {
+            true => Problem::default_type(),
+            false => terror.reference
+        };
+        #[cfg(not(feature = "mdn"))]
+        let problem_type = Problem::default_type();
+
+        #[cfg(feature = "err_id")]
+        let instance = match terror.id.is_nil() {
+            true => None,
+            false => Some(format!("{}{}", UUID_URN_PREFIX, terror.id))
+        };
+        #[cfg(not(feature = "err_id"))]
+        let instance = None;
+
+        Problem {
+            problem_type,
+            title: terror.short_message,
+            status: Some(terror.status),
+            detail: Some(terror.message),
+            instance,
+            extensions,
+        }
+    }
+}
+
+impl From<Problem> for Terror {
+
+    /// Restores a [Terror] from the problem details.
+    /// Assumes `500` if `status` is absent and uses
+    /// `title` as the message if `detail` is absent.
+    /// Fields the problem does not carry are filled
+    /// in the same way as in [Terror::new].
+    fn from(problem: Problem) -> Self {
+        let mut extensions = problem.extensions;
+        let status = problem.status.unwrap_or(500);
+        let message = problem.detail
+            .or_else(|| problem.title.clone())
+            .unwrap_or_default();
+
+        let mut builder = Terror::new(status, message);
+        if let Some(title) = problem.title {
+            builder = builder.shorthand(title);
+        }
+        let nested = match extensions.remove(DETAILS_MEMBER) {
+            Some(Value::Object(nested)) => nested.into_iter().collect(),
+            Some(other) => HashMap::from([(String::from(DETAILS_MEMBER), other)]),
+            None => HashMap::new()
+        };
+        match extensions.remove(ERROR_CODE_MEMBER) {
+            Some(Value::String(code)) => builder = builder.error_code(code),
+            Some(other) => builder = builder.add_value_detail(ERROR_CODE_MEMBER, other),
+            None => {}
+        }
+        if let Some(violations) = extensions.remove(VIOLATIONS_MEMBER) {
+            match serde_json::from_value::<Vec<Violation>>(violations.clone()) {
+                Ok(violations) => builder = builder.add_violations(violations),
+                Err(_) => builder = builder.add_value_detail(VIOLATIONS_MEMBER, violations)
+            }
+        }
+        if let Some(upstream) = extensions.remove(UPSTREAM_MEMBER) {
+            match serde_json::from_value::<Vec<Terror>>(upstream.clone()) {
+                Ok(upstream) => builder = builder.add_upstreams(upstream),
+                Err(_) => builder = builder.add_value_detail(UPSTREAM_MEMBER, upstream)
+            }
+        }
+        if let Some(errors) = extensions.remove(ERRORS_MEMBER) {
+            match serde_json::from_value::<Vec<ItemError>>(errors.clone()) {
+                Ok(errors) => builder = builder.add_item_errors(errors),
+                Err(_) => builder = builder.add_value_detail(ERRORS_MEMBER, errors)
+            }
+        }
+        if let Some(value) = extensions.remove(TEMPLATE_MEMBER) {
+            match serde_json::from_value::<Template>(value.clone()) {
+                Ok(template) => builder.template = Some(template),
+                Err(_) => builder = builder.add_value_detail(TEMPLATE_MEMBER, value)
+            }
+        }
+
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        if let Some(value) = extensions.remove(TIMESTAMP_MEMBER) {
+            match timestamp::from_value(value.clone()) {
+                Some(ts) => builder = builder.timestamp(ts),
+                None => builder = builder.add_value_detail(TIMESTAMP_MEMBER, value)
+            }
+        }
+
+        if problem.problem_type != ABOUT_BLANK {
+            #[cfg(feature = "mdn")]
+            { builder = builder.reference(problem.problem_type); }
+            #[cfg(not(feature = "mdn"))]
+            { builder = builder.add_text_detail("type", problem.problem_type); }
+        }
+
+        if let Some(instance) = problem.instance {
+            #[cfg(feature = "err_id")]
+            match instance.strip_prefix(UUID_URN_PREFIX).and_then(|id| Uuid::parse_str(id).ok()) {
+                Some(id) => builder = builder.id(id),
+                None => builder = builder.add_text_detail("instance", instance)
+            }
+            #[cfg(not(feature = "err_id"))]
+            { builder = builder.add_text_detail("instance", instance); }
+        }
+
+        for (key, value) in extensions.into_iter().chain(nested) {
+            builder = builder.add_value_detail(key, value);
+        }
+        builder.build()
+    }
+
+}
+
+#[cfg(test)]
+mod problem_test {
+    use serde_json::{json, Value};
+    use crate::batch::ItemError;
+    use crate::{MissingKey, Problem, Terror, Violation};
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn serialize_from_terror() -> R {
+        let problem: Problem = Terror::new(404, "no such user")
+            .shorthand("Not Found")
+            .error_code("user.missing")
+            .add_int_detail("user_id", 25)
+            .build()
+            .into();
+
+        let expected = json!({
+            "title": "Not Found",
+            "status": 404,
+            "detail": "no such user",
+            "error_code": "user.missing",
+            "user_id": 25
+        });
+        let actual = serde_json::to_value(problem)?;
+        compare(expected, actual)
+    }
+
+    #[test]
+    fn reserved_details_not_flattened() -> R {
+        let problem: Problem = Terror::new(400, "bad input")
+            .add_text_detail("status", "shadowed")
+            .add_text_detail("field", "name")
+            .build()
+            .into();
+
+        assert_eq!(Some(400), problem.status);
+        assert!(!problem.extensions.contains_key("status"));
+        assert_eq!(Some(&json!("name")), problem.extensions.get("field"));
+        assert_eq!(Some(&json!({ "status": "shadowed" })), problem.extensions.get("details"));
+        Ok(())
+    }
+
+    #[test]
+    fn colliding_details_round_trip() -> R {
+        let original = Terror::new(400, "bad input")
+            .add_text_detail("type", "user")
+            .add_text_detail("error_code", "shadowed")
+            .add_int_detail("details", 1)
+            .build();
+
+        let problem: Problem = original.clone().into();
+        assert!(!problem.extensions.contains_key("error_code"));
+
+        let restored: Terror = problem.into();
+        assert_eq!(None, restored.error_code);
+        assert_eq!(original.details, restored.details);
+        Ok(())
+    }
+
+    #[test]
+    fn foreign_members_kept_as_details() -> R {
+        let inbound = json!({
+            "status": 400,
+            "instance": "/account/12345",
+            "error_code": 42,
+            "timestamp": "yesterday"
+        });
+
+        let problem: Problem = serde_json::from_value(inbound)?;
+        let terror: Terror = problem.into();
+
+        assert_eq!(None, terror.error_code);
+        assert_eq!(Some(&json!("/account/12345")), terror.details.get("instance"));
+        assert_eq!(Some(&json!(42)), terror.details.get("error_code"));
+        assert_eq!(Some(&json!("yesterday")), terror.details.get("timestamp"));
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_into_terror() -> R {
+        let inbound = json!({
+            "type": "https://example.com/probs/out-of-credit",
+            "title": "You do not have enough credit.",
+            "status": 403,
+            "detail": "Your current balance is 30, but that costs 50.",
+            "instance": "/account/12345/msgs/abc",
+            "error_code": "credit.exhausted",
+            "balance": 30
+        });
+
+        let problem: Problem = serde_json::from_value(inbound)?;
+        let terror: Terror = problem.into();
+
+        assert_eq!(403, terror.status);
+        assert_eq!("Your current balance is 30, but that costs 50.", terror.message);
+        assert_eq!(Some(String::from("You do not have enough credit.")), terror.short_message);
+        assert_eq!(Some(String::from("credit.exhausted")), terror.error_code);
+        assert_eq!(Some(&json!(30)), terror.details.get("balance"));
+        assert_eq!(Some(&json!("/account/12345/msgs/abc")), terror.details.get("instance"));
+
+        #[cfg(feature = "mdn")]
+        assert_eq!("https://example.com/probs/out-of-credit", terror.reference);
+        #[cfg(not(feature = "mdn"))]
+        assert_eq!(Some(&json!("https://example.com/probs/out-of-credit")), terror.details.get("type"));
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_minimal() -> R {
+        let problem: Problem = serde_json::from_value(json!({}))?;
+        assert_eq!("about:blank", problem.problem_type);
+
+        let terror: Terror = problem.into();
+        assert_eq!(500, terror.status);
+        assert_eq!("", terror.message);
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> R {
+        let original = Terror::new(409, "version {version} conflict")
+            .shorthand("Conflict")
+            .error_code("entity.conflict")
+            .add_bool_detail("retryable", false)
+            .add_int_detail("version", 3)
+            .add_violation(Violation::new("version", "is stale").code("stale"))
+            .add_upstream(Terror::new(409, "stale version").build())
+            .add_item_error(ItemError::new(0, Terror::new(409, "stale version").build()))
+            .interpolate(MissingKey::Keep)
+            .keep_template()
+            .build();
+
+        let problem: Problem = original.clone().into();
+        let json = serde_json::to_string(&problem)?;
+        let restored: Terror = serde_json::from_str::<Problem>(&json)?.into();
+
+        #[cfg(feature = "time")]
+        assert_eq!(
+            original.timestamp.timestamp_micros(),
+            restored.timestamp.timestamp_micros()
+        );
+        #[cfg(feature = "err_id")]
+        assert_eq!(original.id, restored.id);
+
+        compare(
+            serde_json::to_value(original)?,
+            serde_json::to_value(restored)?
+        )
+    }
+
+    fn compare(mut expected: Value, mut actual: Value) -> R {
+        for value in [&mut expected, &mut actual] {
+            let object = value.as_object_mut().unwrap();
+            object.remove("timestamp");
+            object.remove("id");
+            object.remove("instance");
+            object.remove("reference");
+            object.remove("type");
+        }
+
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+}
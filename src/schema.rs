@@ -0,0 +1,186 @@
+//! JSON Schema of error bodies.
+//!
+//! With feature `schemars` enabled, [Terror] and the
+//! types it nests implement [JsonSchema], so that error
+//! bodies may be validated in contract tests.
+//!
+//! [generate] and [export] describe error bodies as
+//! serialised by this build: fields enabled by features
+//! are present only if the features are enabled, and
+//! not required, as [placeholders](crate::strict) are
+//! never serialised; timestamps are described in the
+//! process-wide format, see `timestamp::set_format`.
+//! The schema is deterministic, so that it may be
+//! checked into a repository and compared with.
+//! Errors with another [Details] type
+//! are described by [generate_for] and [export_for].
+//!
+//! Note that [schema_for!](schemars::schema_for)
+//! describes the input accepted when deserialising
+//! instead, where fields enabled by features may be
+//! absent; see [strict](crate::strict).
+//!
+//! ### Examples
+//!
+//! ```rust
+//! let schema = terror::schema::export().unwrap();
+//! assert!(schema.contains(r#""title": "Terror""#));
+//! ```
+
+use std::collections::HashMap;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use serde_json::Value;
+
+use crate::{Details, Terror};
+
+/// Generates the schema of serialised error bodies.
+pub fn generate() -> Schema {
+    generate_for::<HashMap<String, Value>>()
+}
+
+/// Generates the schema of serialised error bodies
+/// with details of type `D`.
+pub fn generate_for<D: Details + JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .for_serialize()
+        .into_generator()
+        .into_root_schema_for::<Terror<D>>()
+}
+
+/// Generates the schema of serialised error bodies
+/// as pretty-printed JSON, ending with a newline.
+pub fn export() -> Result<String, serde_json::Error> {
+    export_for::<HashMap<String, Value>>()
+}
+
+/// Generates the schema of serialised error bodies
+/// with details of type `D` as pretty-printed JSON,
+/// ending with a newline.
+pub fn export_for<D: Details + JsonSchema>() -> Result<String, serde_json::Error> {
+    let mut exported = serde_json::to_string_pretty(&generate_for::<D>())?;
+    exported.push('\n');
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod schema_test {
+    use serde_json::{json, Value};
+    use crate::schema::{export, generate, generate_for};
+
+    type R = anyhow::Result<()>;
+
+    fn required() -> Vec<Value> {
+        generate().get("required")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn schema_of_terror() {
+        let schema = generate();
+        let properties = &schema.as_value()["properties"];
+
+        assert_eq!(json!("integer"), properties["status"]["type"]);
+        assert_eq!(json!("string"), properties["message"]["type"]);
+        assert_eq!(json!("#"), properties["upstream"]["items"]["$ref"]);
+        assert_eq!(json!("#/$defs/Violation"), properties["violations"]["items"]["$ref"]);
+        assert_eq!(json!("#/$defs/ItemError"), properties["errors"]["items"]["$ref"]);
+        assert_eq!(
+            json!("Error details; arbitrary JSON values by name unless another details type is chosen"),
+            properties["details"]["description"]
+        );
+        assert!(properties.get("source").is_none());
+
+        let required = required();
+        assert!(required.contains(&json!("status")));
+        assert!(!required.contains(&json!("details")));
+    }
+
+    #[test]
+    fn schema_of_feature_fields() {
+        let schema = generate();
+        let properties = &schema.as_value()["properties"];
+        let required = required();
+
+        assert_eq!(cfg!(feature = "err_id"), properties.get("id").is_some());
+        assert_eq!(cfg!(feature = "mdn"), properties.get("reference").is_some());
+        assert_eq!(
+            cfg!(any(feature = "time", feature = "time_crate")),
+            properties.get("timestamp").is_some()
+        );
+        assert!(!required.contains(&json!("id")));
+        assert!(!required.contains(&json!("reference")));
+        assert!(!required.contains(&json!("timestamp")));
+    }
+
+    #[test]
+    fn schema_of_typed_details() {
+        #[derive(serde_derive::Serialize, serde_derive::Deserialize, schemars::JsonSchema)]
+        struct Quota {
+            limit: u32
+        }
+
+        let schema = generate_for::<Quota>();
+        let properties = &schema.as_value()["properties"];
+        let defs = &schema.as_value()["$defs"];
+
+        assert_eq!(json!("#/$defs/Quota"), properties["details"]["$ref"]);
+        assert_eq!(json!("integer"), defs["Quota"]["properties"]["limit"]["type"]);
+        assert_eq!(json!("#/$defs/Terror"), properties["upstream"]["items"]["$ref"]);
+        assert_eq!(json!("object"), defs["Terror"]["properties"]["details"]["type"]);
+    }
+
+    #[test]
+    fn export_schema() -> R {
+        let exported = export()?;
+
+        assert!(exported.ends_with("}\n"));
+        assert_eq!(exported, export()?);
+        assert_eq!(generate().as_value(), &serde_json::from_str::<Value>(&exported)?);
+        Ok(())
+    }
+
+    #[test]
+    fn validate_against_schema() -> R {
+        use crate::batch::Batch;
+        use crate::{Terror, Violation};
+
+        let validator = jsonschema::validator_for(generate().as_value())
+            .map_err(|err| anyhow::anyhow!("invalid schema: {}", err))?;
+        let upstream = Terror::new(503, "billing is down")
+            .build();
+        let bodies = [
+            Terror::new(500, "generic error")
+                .build(),
+            Terror::new(422, "invalid user")
+                .shorthand("Invalid user")
+                .error_code("user.invalid")
+                .add_text_detail("field", "age")
+                .add_violation(Violation::new("age", "must be positive"))
+                .build(),
+            Terror::new(502, "payment failed")
+                .add_upstream(upstream)
+                .build(),
+            Batch::new("some users were not created")
+                .add(0, Terror::new(409, "user exists").build())
+                .add("bob", Terror::new(422, "invalid email").build())
+                .build()
+                .unwrap(),
+        ];
+
+        for built in bodies {
+            let body = serde_json::to_value(&built)?;
+            if let Err(err) = validator.validate(&body) {
+                panic!("{} does not match the schema: {}", body, err);
+            }
+        }
+
+        let mut invalid = serde_json::to_value(Terror::new(500, "generic error").build())?;
+        invalid["status"] = json!("500");
+        assert!(!validator.is_valid(&invalid));
+        Ok(())
+    }
+
+}
@@ -0,0 +1,64 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+/// The original error a [Terror](crate::Terror)
+/// was created from, if any.
+///
+/// Kept solely for the sake of logging and
+/// error chain inspection via [Error::source];
+/// it is never serialised and does not take
+/// part in equality checks, so two objects
+/// reporting the same data are equal regardless
+/// of their sources. Accordingly, any two
+/// [ErrorSource]s are equal, including an empty
+/// one and one wrapping an error.
+#[derive(Clone, Default)]
+pub struct ErrorSource(Option<Arc<dyn Error + Send + Sync + 'static>>);
+
+impl ErrorSource {
+
+    /// Wraps the provided error.
+    pub fn new<E: Error + Send + Sync + 'static>(err: E) -> ErrorSource {
+        ErrorSource(Some(Arc::new(err)))
+    }
+
+    /// Returns the wrapped error, if any.
+    pub fn get(&self) -> Option<&(dyn Error + Send + Sync + 'static)> {
+        self.0.as_deref()
+    }
+
+    /// Tells whether there is no wrapped error.
+    pub fn is_none(&self) -> bool {
+        self.0.is_none()
+    }
+
+}
+
+impl From<Box<dyn Error + Send + Sync + 'static>> for ErrorSource {
+    fn from(err: Box<dyn Error + Send + Sync + 'static>) -> Self {
+        ErrorSource(Some(Arc::from(err)))
+    }
+}
+
+impl Debug for ErrorSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(err) => write!(f, "Some({:?})", err),
+            None => write!(f, "None")
+        }
+    }
+}
+
+impl PartialEq for ErrorSource {
+
+    /// Always `true`, as sources are not
+    /// a part of the error report.
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+
+}
+
+impl Eq for ErrorSource {}
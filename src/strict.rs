@@ -0,0 +1,381 @@
+//! Strict deserialisation of [Terror].
+//!
+//! By default, [Terror] deserialises leniently: fields
+//! enabled by features (`id`, `timestamp`, `reference`)
+//! may be absent from the input, see
+//! [interoperability](crate#interoperability). Absent
+//! fields are filled as follows:
+//! * `id` - nil UUID;
+//! * `timestamp` - Unix epoch;
+//! * `reference` - empty string.
+//!
+//! These are mere placeholders, so they are never
+//! serialised: a relayed error body omits the fields
+//! instead of reporting made-up values. As placeholders
+//! may just as well have been received, [Received]
+//! tells whether each of the fields was present in
+//! the input.
+//!
+//! This module also provides the opposite behaviour,
+//! where every feature-gated field must be present.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use serde_json::json;
+//! let inbound = json!({ "status": 404, "message": "no such user" });
+//! let strict = terror::strict::deserialize(inbound);
+//! # #[cfg(not(any(feature = "err_id", feature = "time", feature = "time_crate", feature = "mdn")))]
+//! assert!(strict.is_ok());
+//! # #[cfg(any(feature = "err_id", feature = "time", feature = "time_crate", feature = "mdn"))]
+//! # assert!(strict.is_err());
+//! ```
+//!
+//! It also fits `#[serde(deserialize_with = "terror::strict::deserialize")]`.
+//!
+//! Whereas [Received] deserialises leniently, but
+//! reports the fields as they were received:
+//!
+//! ```rust
+//! use serde_json::json;
+//! use terror::strict::Received;
+//! let inbound = json!({ "status": 404, "message": "no such user" });
+//! let received: Received = serde_json::from_value(inbound).unwrap();
+//! assert_eq!(404, received.terror.status);
+//! # #[cfg(feature = "err_id")]
+//! assert!(received.id.is_none());
+//! ```
+
+use std::collections::HashMap;
+use serde::Deserializer;
+use serde::Deserialize as Deserializable;
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+#[cfg(any(feature = "time", feature = "time_crate"))]
+use crate::timestamp::{self, Timestamp};
+#[cfg(feature = "err_id")]
+use uuid::Uuid;
+
+use crate::batch::ItemError;
+use crate::{detail, Details, ErrorSource, Template, Terror, Violation};
+
+/// Deserialises a [Terror], failing if any of
+/// the fields enabled by features is absent.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Terror, D::Error> {
+    #[allow(unused_imports)]
+    use serde::de::Error;
+
+    let received = Received::deserialize(deserializer)?;
+
+    #[cfg(feature = "mdn")]
+    if received.reference.is_none() {
+        return Err(D::Error::missing_field("reference"));
+    }
+
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    if received.timestamp.is_none() {
+        return Err(D::Error::missing_field("timestamp"));
+    }
+
+    #[cfg(feature = "err_id")]
+    if received.id.is_none() {
+        return Err(D::Error::missing_field("id"));
+    }
+
+    Ok(received.terror)
+}
+
+/// A leniently deserialised [Terror] along with
+/// the fields enabled by features, as present in
+/// the input, so that absent fields can be told
+/// apart from placeholders.
+#[derive(Debug, Deserialize, Eq, PartialEq, Clone)]
+#[serde(try_from = "TerrorRepr<D>", bound(deserialize = "D: Details"))]
+#[non_exhaustive]
+pub struct Received<D = HashMap<String, Value>> {
+
+    /// Error body, absent fields filled in
+    pub terror: Terror<D>,
+
+    /// Received reference; nullable
+    #[cfg(feature = "mdn")]
+    pub reference: Option<String>,
+
+    /// Received timestamp; nullable
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    pub timestamp: Option<Timestamp>,
+
+    /// Received error ID; nullable
+    #[cfg(feature = "err_id")]
+    pub id: Option<Uuid>
+
+}
+
+impl<D: Details> TryFrom<TerrorRepr<D>> for Received<D> {
+    type Error = &'static str;
+
+    fn try_from(repr: TerrorRepr<D>) -> Result<Self, Self::Error> {
+        #[cfg(feature = "mdn")]
+        let reference = repr.reference.clone();
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        let timestamp = repr.timestamp;
+        #[cfg(feature = "err_id")]
+        let id = repr.id;
+
+        Ok(Received {
+            terror: repr.try_into()?,
+
+            #[cfg(feature = "mdn")]
+            reference,
+
+            #[cfg(any(feature = "time", feature = "time_crate"))]
+            timestamp,
+
+            #[cfg(feature = "err_id")]
+            id,
+        })
+    }
+}
+
+/// Wire representation of [Terror], which
+/// tolerates absent feature-gated fields.
+#[derive(Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "D: Details + schemars::JsonSchema"))]
+#[serde(bound(deserialize = "D: Details"))]
+pub(crate) struct TerrorRepr<D = HashMap<String, Value>> {
+
+    status: u16,
+
+    message: String,
+
+    short_message: Option<String>,
+
+    error_code: Option<String>,
+
+    #[serde(default)]
+    details: Option<D>,
+
+    #[serde(default)]
+    violations: Vec<Violation>,
+
+    #[serde(default)]
+    upstream: Vec<Terror>,
+
+    #[serde(default)]
+    errors: Vec<ItemError>,
+
+    template: Option<Template>,
+
+    #[cfg(feature = "mdn")]
+    reference: Option<String>,
+
+    #[cfg(any(feature = "time", feature = "time_crate"))]
+    #[serde(default, deserialize_with = "crate::timestamp::deserialize_option")]
+    #[cfg_attr(feature = "schemars", schemars(schema_with = "crate::timestamp::json_schema_option"))]
+    timestamp: Option<Timestamp>,
+
+    #[cfg(feature = "err_id")]
+    id: Option<Uuid>
+
+}
+
+impl<D: Details> TryFrom<TerrorRepr<D>> for Terror<D> {
+    type Error = &'static str;
+
+    /// Fails if details are absent, but neither
+    /// `null` nor an empty object fit their type.
+    fn try_from(repr: TerrorRepr<D>) -> Result<Self, Self::Error> {
+        let details = repr.details
+            .or_else(detail::absent)
+            .ok_or("missing field `details`")?;
+        Ok(Terror {
+            status: repr.status,
+            message: repr.message,
+            short_message: repr.short_message,
+            error_code: repr.error_code,
+            details,
+            violations: repr.violations,
+            upstream: repr.upstream,
+            errors: repr.errors,
+            template: repr.template,
+
+            #[cfg(feature = "mdn")]
+            reference: repr.reference
+                .unwrap_or_default(),
+
+            #[cfg(any(feature = "time", feature = "time_crate"))]
+            timestamp: repr.timestamp
+                .unwrap_or_else(timestamp::epoch),
+
+            #[cfg(feature = "err_id")]
+            id: repr.id
+                .unwrap_or(Uuid::nil()),
+
+            source: ErrorSource::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod strict_test {
+    use serde_json::json;
+    use crate::strict::Received;
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn lenient_without_feature_fields() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user",
+            "error_code": "user.missing"
+        });
+
+        let as_struct: Terror = serde_json::from_value(inbound)?;
+
+        assert_eq!(404, as_struct.status);
+        assert_eq!("no such user", as_struct.message);
+        assert_eq!(Some(String::from("user.missing")), as_struct.error_code);
+
+        #[cfg(feature = "mdn")]
+        assert_eq!("", as_struct.reference);
+        #[cfg(feature = "time")]
+        assert_eq!(0, as_struct.timestamp.timestamp());
+        #[cfg(feature = "err_id")]
+        assert!(as_struct.id.is_nil());
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_omits_placeholders() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user"
+        });
+
+        let as_struct: Terror = serde_json::from_value(inbound.clone())?;
+
+        assert_eq!(inbound, serde_json::to_value(&as_struct)?);
+        Ok(())
+    }
+
+    #[test]
+    fn received_without_feature_fields() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user"
+        });
+
+        let received: Received = serde_json::from_value(inbound)?;
+
+        assert_eq!(404, received.terror.status);
+        #[cfg(feature = "mdn")]
+        assert_eq!(None, received.reference);
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        assert_eq!(None, received.timestamp);
+        #[cfg(feature = "err_id")]
+        assert_eq!(None, received.id);
+        Ok(())
+    }
+
+    #[test]
+    fn received_with_feature_fields() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user",
+            "reference": "https://example.com/errors/user-missing",
+            "timestamp": "2022-01-01T21:00:00Z",
+            "id": "00000000-0000-0000-0000-000000000000"
+        });
+
+        let received: Received = serde_json::from_value(inbound)?;
+
+        assert_eq!(404, received.terror.status);
+        #[cfg(feature = "mdn")]
+        assert_eq!(Some(received.terror.reference.clone()), received.reference);
+        #[cfg(any(feature = "time", feature = "time_crate"))]
+        assert_eq!(Some(received.terror.timestamp), received.timestamp);
+        #[cfg(feature = "err_id")]
+        assert_eq!(Some(uuid::Uuid::nil()), received.id);
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_ignores_unknown_fields() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user",
+            "reference": "https://developer.mozilla.org/en-US/docs/Web/HTTP/Status/404",
+            "timestamp": "2022-01-01T21:00:00Z",
+            "id": "2d10a950-d6f4-11ec-ab97-00155d887325",
+            "unknown": true
+        });
+
+        let as_struct: Terror = serde_json::from_value(inbound)?;
+
+        assert_eq!(404, as_struct.status);
+        #[cfg(feature = "time")]
+        assert_eq!(1641070800, as_struct.timestamp.timestamp());
+        #[cfg(feature = "err_id")]
+        assert_eq!("2d10a950-d6f4-11ec-ab97-00155d887325", as_struct.id.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn strict_with_feature_fields() -> R {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user",
+            "reference": "https://example.com/errors/user-missing",
+            "timestamp": "2022-01-01T21:00:00Z",
+            "id": "2d10a950-d6f4-11ec-ab97-00155d887325"
+        });
+
+        let as_struct = crate::strict::deserialize(inbound)?;
+
+        assert_eq!(404, as_struct.status);
+        #[cfg(feature = "mdn")]
+        assert_eq!("https://example.com/errors/user-missing", as_struct.reference);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(any(feature = "err_id", feature = "time", feature = "time_crate", feature = "mdn"))]
+    fn strict_without_feature_fields() {
+        let inbound = json!({
+            "status": 404,
+            "message": "no such user"
+        });
+
+        let err = crate::strict::deserialize(inbound)
+            .unwrap_err();
+        assert!(err.to_string().starts_with("missing field"));
+    }
+
+    #[test]
+    fn strict_in_serde_attribute() -> R {
+        #[derive(serde_derive::Deserialize)]
+        struct Envelope {
+            #[serde(deserialize_with = "crate::strict::deserialize")]
+            error: Terror
+        }
+
+        let inbound = json!({
+            "error": {
+                "status": 500,
+                "message": "generic error",
+                "reference": "https://developer.mozilla.org/en-US/docs/Web/HTTP/Status/500",
+                "timestamp": "2022-01-01T21:00:00Z",
+                "id": "2d10a950-d6f4-11ec-ab97-00155d887325"
+            }
+        });
+
+        let envelope: Envelope = serde_json::from_value(inbound)?;
+        assert_eq!(500, envelope.error.status);
+        Ok(())
+    }
+
+}
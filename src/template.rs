@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+
+/// Handling of placeholders, which name no
+/// detail; see [Builder::interpolate](crate::Builder::interpolate).
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MissingKey {
+
+    /// Fail [Builder::try_build](crate::Builder::try_build);
+    /// [Builder::build](crate::Builder::build), which
+    /// never fails, keeps such placeholders as they are
+    Error,
+
+    /// Keep placeholders as they are
+    Keep,
+
+    /// Replace placeholders with nothing
+    Blank
+
+}
+
+/// Raw message templates along with the values
+/// of their placeholders, so that clients may
+/// localise messages; see
+/// [Builder::keep_template](crate::Builder::keep_template).
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Template {
+
+    /// Template of the full error message
+    pub message: String,
+
+    /// Template of the shortened error message; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub short_message: Option<String>,
+
+    /// Values of the resolved placeholders by name
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Object))]
+    pub args: HashMap<String, Value>
+
+}
+
+/// Failure to resolve a placeholder via
+/// [Builder::try_build](crate::Builder::try_build)
+/// under [MissingKey::Error].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TemplateError {
+    key: String
+}
+
+impl TemplateError {
+
+    /// Reports the name of the placeholder.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "no detail for placeholder '{}'", self.key)
+    }
+}
+
+impl Error for TemplateError {}
+
+/// Resolution of placeholders against details.
+pub(crate) struct Rendering<'a> {
+    details: &'a Value,
+    policy: MissingKey,
+    args: HashMap<String, Value>,
+    missing: Option<String>
+}
+
+impl<'a> Rendering<'a> {
+
+    pub(crate) fn new(details: &'a Value, policy: MissingKey) -> Rendering<'a> {
+        Rendering {
+            details,
+            policy,
+            args: HashMap::new(),
+            missing: None
+        }
+    }
+
+    /// Resolves `{name}` placeholders; `{{` and `}}`
+    /// stand for literal braces, and unmatched braces
+    /// are kept as they are.
+    pub(crate) fn render(&mut self, template: &str) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(at) = rest.find(['{', '}']) {
+            rendered.push_str(&rest[..at]);
+            rest = &rest[at..];
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                rendered.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let placeholder = match rest.starts_with('{') {
+                true => rest[1..].find(['{', '}'])
+                    .filter(|&end| rest[1 + end..].starts_with('}'))
+                    .map(|end| &rest[1..1 + end])
+                    .filter(|key| !key.is_empty()),
+                false => None
+            };
+            match placeholder {
+                Some(key) => {
+                    self.resolve(key, &mut rendered);
+                    rest = &rest[key.len() + 2..];
+                },
+                None => {
+                    rendered.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    fn resolve(&mut self, key: &str, rendered: &mut String) {
+        match self.details.get(key) {
+            Some(value) => {
+                match value {
+                    Value::String(text) => rendered.push_str(text),
+                    _ => rendered.push_str(&value.to_string())
+                }
+                self.args.insert(String::from(key), value.clone());
+            },
+            None => {
+                if self.policy != MissingKey::Blank {
+                    rendered.push('{');
+                    rendered.push_str(key);
+                    rendered.push('}');
+                }
+                if self.missing.is_none() {
+                    self.missing = Some(String::from(key));
+                }
+            }
+        }
+    }
+
+    /// Concludes the resolution into the values of
+    /// the resolved placeholders and, if the policy
+    /// says so, the failure to resolve the first of
+    /// any other.
+    pub(crate) fn finish(self) -> (HashMap<String, Value>, Option<TemplateError>) {
+        let failure = match self.policy {
+            MissingKey::Error => self.missing.map(|key| TemplateError { key }),
+            MissingKey::Keep | MissingKey::Blank => None
+        };
+        (self.args, failure)
+    }
+
+}
+
+#[cfg(test)]
+mod template_test {
+    use serde_json::json;
+    use crate::template::{MissingKey, Rendering};
+
+    #[test]
+    fn render_placeholders() {
+        let details = json!({ "user": "alice", "age": 42, "admin": false });
+        let mut rendering = Rendering::new(&details, MissingKey::Keep);
+
+        assert_eq!(
+            "user alice (42, false)",
+            rendering.render("user {user} ({age}, {admin})")
+        );
+        assert_eq!(3, rendering.finish().0.len());
+    }
+
+    #[test]
+    fn render_braces() {
+        let details = json!({ "user": "alice" });
+        let mut rendering = Rendering::new(&details, MissingKey::Keep);
+
+        assert_eq!("{user} is alice", rendering.render("{{user}} is {user}"));
+        assert_eq!("{ {} } alice}", rendering.render("{ {} } {user}}"));
+        assert_eq!("{", rendering.render("{"));
+    }
+
+    #[test]
+    fn render_missing() {
+        let details = json!({});
+
+        let mut keeping = Rendering::new(&details, MissingKey::Keep);
+        assert_eq!("no such user: {user}", keeping.render("no such user: {user}"));
+        assert!(keeping.finish().0.is_empty());
+
+        let mut blanking = Rendering::new(&details, MissingKey::Blank);
+        assert_eq!("no such user: ", blanking.render("no such user: {user}"));
+        assert!(blanking.finish().1.is_none());
+
+        let mut failing = Rendering::new(&details, MissingKey::Error);
+        assert_eq!("no such user: {user}", failing.render("no such user: {user}"));
+        assert_eq!("user", failing.finish().1.unwrap().key());
+    }
+
+}
@@ -0,0 +1,528 @@
+//! Timestamps for features `time` and `time_crate`.
+//!
+//! ### Backends
+//!
+//! Feature `time` stores timestamps as
+//! `chrono::DateTime<Utc>`, while feature `time_crate`
+//! stores them as `time::OffsetDateTime`; see [Timestamp].
+//! As features are unified across the dependency graph,
+//! both may end up enabled; then `time` takes precedence
+//! and `time_crate` has no effect. The wire format is
+//! the same either way, see
+//! [interoperability](crate#interoperability).
+//!
+//! ### Encoding
+//!
+//! By default, timestamps are serialised as RFC 3339
+//! strings at UTC with as many fractional digits as
+//! needed, in groups of 3. Another [TimestampFormat]
+//! may be selected process-wide via [set_format].
+//!
+//! Deserialisation accepts any of the supported encodings
+//! regardless of the selected one: RFC 3339 strings,
+//! integer epoch seconds or milliseconds and fractional
+//! epoch seconds. Integers are taken for seconds or
+//! milliseconds as per the selected format, if it is
+//! either of these. Otherwise, integers of 12 digits and
+//! longer are taken for milliseconds, as in seconds they
+//! would point past the year 5000.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::timestamp::{Precision, TimestampFormat};
+//! terror::timestamp::set_format(TimestampFormat::Rfc3339Fixed(Precision::Millis));
+//! ```
+
+use std::fmt::Write;
+use std::sync::{PoisonError, RwLock};
+use serde::{Deserializer, Serializer};
+use serde::Deserialize as Deserializable;
+use serde_derive::Deserialize;
+use serde_json::Value;
+
+#[cfg(feature = "schemars")]
+use schemars::{json_schema, Schema, SchemaGenerator};
+#[cfg(feature = "utoipa")]
+use utoipa::openapi::schema::{KnownFormat, Object, ObjectBuilder, SchemaFormat, Type};
+
+/// Timestamp type of the `chrono` backend.
+#[cfg(feature = "time")]
+pub type Timestamp = chrono::DateTime<chrono::Utc>;
+
+/// Timestamp type of the `time` backend.
+#[cfg(all(feature = "time_crate", not(feature = "time")))]
+pub type Timestamp = time::OffsetDateTime;
+
+/// Serialised form of timestamps.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum TimestampFormat {
+
+    /// RFC 3339 string with as many fractional
+    /// digits as needed, in groups of 3, e.g.
+    /// `2022-01-01T21:00:00.500Z`; the default
+    #[default]
+    Rfc3339,
+
+    /// RFC 3339 string with a fixed number of
+    /// fractional digits, e.g. `2022-01-01T21:00:00.500Z`
+    /// with [Precision::Millis]
+    Rfc3339Fixed(Precision),
+
+    /// Integer number of seconds since Unix epoch
+    EpochSeconds,
+
+    /// Integer number of milliseconds since Unix epoch
+    EpochMillis
+
+}
+
+/// Number of fractional digits of
+/// [TimestampFormat::Rfc3339Fixed].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Precision {
+
+    /// No fractional digits
+    Seconds,
+
+    /// 3 fractional digits
+    Millis,
+
+    /// 6 fractional digits
+    Micros,
+
+    /// 9 fractional digits
+    Nanos
+
+}
+
+impl Precision {
+
+    /// Number of fractional digits.
+    fn digits(self) -> u32 {
+        match self {
+            Precision::Seconds => 0,
+            Precision::Millis => 3,
+            Precision::Micros => 6,
+            Precision::Nanos => 9
+        }
+    }
+
+}
+
+/// Process-wide format.
+static FORMAT: RwLock<TimestampFormat> = RwLock::new(TimestampFormat::Rfc3339);
+
+/// Integers starting from this one are taken
+/// for milliseconds rather than seconds, unless
+/// the format tells otherwise.
+const MIN_EPOCH_MILLIS: i64 = 100_000_000_000;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MILLI: i128 = 1_000_000;
+
+/// Selects the process-wide format.
+pub fn set_format(format: TimestampFormat) {
+    *FORMAT.write().unwrap_or_else(PoisonError::into_inner) = format;
+}
+
+/// Reports the process-wide format.
+pub fn format() -> TimestampFormat {
+    *FORMAT.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Calendar representation of a timestamp at UTC.
+struct Parts {
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanos: u32
+}
+
+/// Operations over `chrono` timestamps.
+#[cfg(feature = "time")]
+mod backend {
+    use std::time::Duration;
+    use chrono::{DateTime, Datelike, TimeDelta, TimeZone, Timelike, Utc};
+    use super::{Parts, Timestamp, NANOS_PER_SECOND};
+
+    pub(crate) fn now() -> Timestamp {
+        Utc::now()
+    }
+
+    pub(crate) fn epoch() -> Timestamp {
+        DateTime::<Utc>::UNIX_EPOCH
+    }
+
+    pub(crate) fn add(ts: Timestamp, by: Duration) -> Timestamp {
+        TimeDelta::from_std(by).ok()
+            .and_then(|by| ts.checked_add_signed(by))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    pub(super) fn from_unix_nanos(nanos: i128) -> Option<Timestamp> {
+        let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).ok()?;
+        Utc.timestamp_opt(seconds, nanos.rem_euclid(NANOS_PER_SECOND) as u32).single()
+    }
+
+    pub(super) fn unix_nanos(ts: &Timestamp) -> i128 {
+        ts.timestamp() as i128 * NANOS_PER_SECOND + ts.timestamp_subsec_nanos() as i128
+    }
+
+    pub(super) fn parts(ts: &Timestamp) -> Parts {
+        Parts {
+            year: ts.year(),
+            month: ts.month() as u8,
+            day: ts.day() as u8,
+            hour: ts.hour() as u8,
+            minute: ts.minute() as u8,
+            second: ts.second() as u8,
+            nanos: ts.nanosecond()
+        }
+    }
+
+    pub(crate) fn parse_rfc3339(text: &str) -> Result<Timestamp, String> {
+        DateTime::parse_from_rfc3339(text)
+            .map(|ts| ts.with_timezone(&Utc))
+            .map_err(|err| err.to_string())
+    }
+
+}
+
+/// Operations over `time` timestamps.
+#[cfg(all(feature = "time_crate", not(feature = "time")))]
+mod backend {
+    use std::time::Duration;
+    use time::format_description::well_known::Rfc3339;
+    use time::{OffsetDateTime, PrimitiveDateTime, UtcOffset};
+    use super::{Parts, Timestamp};
+
+    pub(crate) fn now() -> Timestamp {
+        OffsetDateTime::now_utc()
+    }
+
+    pub(crate) fn epoch() -> Timestamp {
+        OffsetDateTime::UNIX_EPOCH
+    }
+
+    pub(crate) fn add(ts: Timestamp, by: Duration) -> Timestamp {
+        time::Duration::try_from(by).ok()
+            .and_then(|by| ts.checked_add(by))
+            .unwrap_or(PrimitiveDateTime::MAX.assume_utc())
+    }
+
+    pub(super) fn from_unix_nanos(nanos: i128) -> Option<Timestamp> {
+        OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+    }
+
+    pub(super) fn unix_nanos(ts: &Timestamp) -> i128 {
+        ts.unix_timestamp_nanos()
+    }
+
+    pub(super) fn parts(ts: &Timestamp) -> Parts {
+        let ts = ts.to_offset(UtcOffset::UTC);
+        Parts {
+            year: ts.year(),
+            month: ts.month() as u8,
+            day: ts.day(),
+            hour: ts.hour(),
+            minute: ts.minute(),
+            second: ts.second(),
+            nanos: ts.nanosecond()
+        }
+    }
+
+    pub(crate) fn parse_rfc3339(text: &str) -> Result<Timestamp, String> {
+        OffsetDateTime::parse(text, &Rfc3339)
+            .map(|ts| ts.to_offset(UtcOffset::UTC))
+            .map_err(|err| err.to_string())
+    }
+
+}
+
+pub(crate) use backend::{add, epoch, now, parse_rfc3339};
+
+/// Tells if the timestamp is the placeholder
+/// filled in when it is absent from the input.
+pub(crate) fn is_epoch(ts: &Timestamp) -> bool {
+    *ts == epoch()
+}
+
+/// Formats the timestamp as RFC 3339 at UTC with the
+/// fixed number of fractional digits, if provided, or
+/// as many as needed in groups of 3 otherwise.
+fn to_rfc3339(ts: &Timestamp, precision: Option<Precision>) -> String {
+    let mut parts = backend::parts(ts);
+    // leap second
+    if parts.nanos >= NANOS_PER_SECOND as u32 {
+        parts.second += 1;
+        parts.nanos -= NANOS_PER_SECOND as u32;
+    }
+
+    let mut text = String::with_capacity(30);
+    let _ = match parts.year {
+        0..=9999 => write!(text, "{:04}", parts.year),
+        _ => write!(text, "{:+05}", parts.year)
+    };
+    let _ = write!(
+        text,
+        "-{:02}-{:02}T{:02}:{:02}:{:02}",
+        parts.month, parts.day, parts.hour, parts.minute, parts.second
+    );
+
+    let digits = match precision {
+        Some(precision) => precision.digits(),
+        None if parts.nanos == 0 => 0,
+        None if parts.nanos % 1_000_000 == 0 => 3,
+        None if parts.nanos % 1_000 == 0 => 6,
+        None => 9
+    };
+    if digits > 0 {
+        let fraction = parts.nanos / 10u32.pow(9 - digits);
+        let _ = write!(text, ".{:0width$}", fraction, width = digits as usize);
+    }
+
+    text.push('Z');
+    text
+}
+
+/// Any of the accepted encodings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Encoded {
+    Int(i64),
+    Float(f64),
+    Text(String)
+}
+
+impl Encoded {
+
+    /// Decodes the timestamp; integers are taken for
+    /// seconds or milliseconds as per the format, or
+    /// by their magnitude for RFC 3339 formats.
+    fn decode(self, format: TimestampFormat) -> Result<Timestamp, String> {
+        let nanos = match (self, format) {
+            (Encoded::Int(value), TimestampFormat::EpochSeconds) => value as i128 * NANOS_PER_SECOND,
+            (Encoded::Int(value), TimestampFormat::EpochMillis) => value as i128 * NANOS_PER_MILLI,
+            (Encoded::Int(value), _) if value.abs() >= MIN_EPOCH_MILLIS => value as i128 * NANOS_PER_MILLI,
+            (Encoded::Int(value), _) => value as i128 * NANOS_PER_SECOND,
+            (Encoded::Float(value), _) if value.is_finite() => {
+                let fraction = (value.fract() * NANOS_PER_SECOND as f64).round() as i128;
+                value.trunc() as i128 * NANOS_PER_SECOND + fraction
+            },
+            (Encoded::Float(value), _) => return Err(format!("invalid epoch seconds: {}", value)),
+            (Encoded::Text(value), _) => return parse_rfc3339(&value)
+                .map_err(|err| format!("invalid RFC 3339 timestamp '{}': {}", value, err))
+        };
+        backend::from_unix_nanos(nanos)
+            .ok_or_else(|| format!("epoch timestamp out of range: {} ns", nanos))
+    }
+
+}
+
+/// Encodes the timestamp into a JSON value
+/// in the process-wide format.
+pub(crate) fn to_value(ts: &Timestamp) -> Value {
+    serialize(ts, serde_json::value::Serializer)
+        .unwrap_or(Value::Null)
+}
+
+/// Decodes the timestamp from a JSON value
+/// in any of the accepted encodings, taking
+/// integers as per the process-wide format.
+pub(crate) fn from_value(value: Value) -> Option<Timestamp> {
+    serde_json::from_value::<Encoded>(value).ok()
+        .and_then(|encoded| encoded.decode(format()).ok())
+}
+
+/// Serialises the timestamp in the process-wide format.
+pub(crate) fn serialize<S: Serializer>(ts: &Timestamp, serializer: S) -> Result<S::Ok, S::Error> {
+    let nanos = backend::unix_nanos(ts);
+    match format() {
+        TimestampFormat::Rfc3339 => serializer.serialize_str(&to_rfc3339(ts, None)),
+        TimestampFormat::Rfc3339Fixed(precision) => serializer
+            .serialize_str(&to_rfc3339(ts, Some(precision))),
+        TimestampFormat::EpochSeconds => serializer
+            .serialize_i64(nanos.div_euclid(NANOS_PER_SECOND) as i64),
+        TimestampFormat::EpochMillis => serializer
+            .serialize_i64(nanos.div_euclid(NANOS_PER_MILLI) as i64)
+    }
+}
+
+/// Deserialises an optional timestamp
+/// in any of the accepted encodings.
+pub(crate) fn deserialize_option<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Timestamp>, D::Error> {
+    match Option::<Encoded>::deserialize(deserializer)? {
+        Some(encoded) => encoded.decode(format())
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        None => Ok(None)
+    }
+}
+
+/// Describes the timestamp as serialised
+/// in the process-wide format.
+#[cfg(feature = "schemars")]
+pub(crate) fn json_schema(_: &mut SchemaGenerator) -> Schema {
+    match format() {
+        TimestampFormat::Rfc3339 | TimestampFormat::Rfc3339Fixed(_) => json_schema!({
+            "type": "string",
+            "format": "date-time"
+        }),
+        TimestampFormat::EpochSeconds | TimestampFormat::EpochMillis => json_schema!({
+            "type": "integer"
+        })
+    }
+}
+
+/// Describes the timestamp as serialised in
+/// the process-wide format, for OpenAPI.
+#[cfg(feature = "utoipa")]
+pub(crate) fn openapi_schema() -> Object {
+    match format() {
+        TimestampFormat::Rfc3339 | TimestampFormat::Rfc3339Fixed(_) => ObjectBuilder::new()
+            .schema_type(Type::String)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::DateTime)))
+            .build(),
+        TimestampFormat::EpochSeconds | TimestampFormat::EpochMillis => ObjectBuilder::new()
+            .schema_type(Type::Integer)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64)))
+            .build()
+    }
+}
+
+/// Describes an optional timestamp in
+/// any of the accepted encodings.
+#[cfg(feature = "schemars")]
+pub(crate) fn json_schema_option(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "anyOf": [
+            { "type": "string", "format": "date-time" },
+            { "type": "number" },
+            { "type": "null" }
+        ]
+    })
+}
+
+#[cfg(test)]
+mod timestamp_test {
+    use serde_json::json;
+    use std::time::Duration;
+    use crate::timestamp::{add, epoch, from_value, parse_rfc3339, to_rfc3339, Encoded, Precision, Timestamp, TimestampFormat};
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn decode_rfc3339() -> R {
+        assert_eq!(at("2022-01-01T21:00:00.5Z")?, decode(json!("2022-01-01T21:00:00.5Z"))?);
+        assert_eq!(at("2022-01-01T21:00:00Z")?, decode(json!("2022-01-02T00:00:00+03:00"))?);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_epoch() -> R {
+        assert_eq!(at("2022-01-01T21:00:00Z")?, decode(json!(1641070800))?);
+        assert_eq!(at("2022-01-01T21:00:00.250Z")?, decode(json!(1641070800250i64))?);
+        assert_eq!(at("2022-01-01T21:00:00.5Z")?, decode(json!(1641070800.5))?);
+        assert_eq!(at("1969-12-31T23:59:59Z")?, decode(json!(-1))?);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_epoch_as_formatted() -> R {
+        let early = at("1971-01-01T00:00:00Z")?;
+
+        assert_eq!(early, decode_as(json!(31536000000i64), TimestampFormat::EpochMillis)?);
+        assert_eq!(early, decode_as(json!(31536000), TimestampFormat::EpochSeconds)?);
+        assert_eq!(at("1970-01-19T23:51:10.800Z")?, decode_as(json!(1641070800), TimestampFormat::EpochMillis)?);
+        assert_eq!(at("2022-01-01T21:00:00.5Z")?, decode_as(json!(1641070800.5), TimestampFormat::EpochMillis)?);
+        Ok(())
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(decode(json!("yesterday")).is_err());
+        assert!(from_value(json!(true)).is_none());
+    }
+
+    #[test]
+    fn deserialize_any_encoding() -> R {
+        for encoded in [json!("2022-01-01T21:00:00Z"), json!(1641070800), json!(1641070800000i64)] {
+            let inbound = json!({
+                "status": 404,
+                "message": "generic error",
+                "timestamp": encoded
+            });
+
+            let as_struct: Terror = serde_json::from_value(inbound)?;
+            assert_eq!(at("2022-01-01T21:00:00Z")?, as_struct.timestamp);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn format_auto_precision() -> R {
+        assert_eq!("2022-01-01T21:00:00Z", to_rfc3339(&at("2022-01-01T21:00:00Z")?, None));
+        assert_eq!("2022-01-01T21:00:00.500Z", to_rfc3339(&at("2022-01-01T21:00:00.5Z")?, None));
+        assert_eq!("2022-01-01T21:00:00.000500Z", to_rfc3339(&at("2022-01-01T21:00:00.0005Z")?, None));
+        assert_eq!("2022-01-01T21:00:00.000000005Z", to_rfc3339(&at("2022-01-01T21:00:00.000000005Z")?, None));
+        assert_eq!("2022-01-01T21:00:00Z", to_rfc3339(&at("2022-01-02T00:00:00+03:00")?, None));
+        Ok(())
+    }
+
+    #[test]
+    fn format_fixed_precision() -> R {
+        let ts = at("2022-01-01T21:00:00.123456789Z")?;
+
+        assert_eq!("2022-01-01T21:00:00Z", to_rfc3339(&ts, Some(Precision::Seconds)));
+        assert_eq!("2022-01-01T21:00:00.123Z", to_rfc3339(&ts, Some(Precision::Millis)));
+        assert_eq!("2022-01-01T21:00:00.123456Z", to_rfc3339(&ts, Some(Precision::Micros)));
+        assert_eq!("2022-01-01T21:00:00.123456789Z", to_rfc3339(&ts, Some(Precision::Nanos)));
+        assert_eq!(TimestampFormat::Rfc3339, TimestampFormat::default());
+        Ok(())
+    }
+
+    #[test]
+    fn add_duration() -> R {
+        assert_eq!(at("1970-01-01T00:01:30.5Z")?, add(epoch(), Duration::from_millis(90_500)));
+        Ok(())
+    }
+
+    #[test]
+    fn add_saturates() -> R {
+        let latest = add(epoch(), Duration::MAX);
+
+        assert!(at("9999-12-31T23:59:59Z")? <= latest);
+        assert_eq!(latest, add(latest, Duration::from_secs(1)));
+        assert_eq!(latest, add(at("2022-01-01T21:00:00Z")?, Duration::from_secs(u64::MAX / 2)));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn format_as_chrono() -> R {
+        for ts in ["2022-01-01T21:00:00Z", "2022-01-01T21:00:00.25Z", "2022-01-01T21:00:00.000001Z", "0001-01-01T00:00:00.1Z"] {
+            let ts = at(ts)?;
+            assert_eq!(serde_json::to_value(ts)?, json!(to_rfc3339(&ts, None)));
+        }
+        Ok(())
+    }
+
+    fn decode(value: serde_json::Value) -> anyhow::Result<Timestamp> {
+        decode_as(value, TimestampFormat::Rfc3339)
+    }
+
+    fn decode_as(value: serde_json::Value, format: TimestampFormat) -> anyhow::Result<Timestamp> {
+        let encoded: Encoded = serde_json::from_value(value)?;
+        encoded.decode(format).map_err(anyhow::Error::msg)
+    }
+
+    fn at(ts: &str) -> anyhow::Result<Timestamp> {
+        parse_rfc3339(ts).map_err(anyhow::Error::msg)
+    }
+
+}
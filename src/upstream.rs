@@ -0,0 +1,368 @@
+//! Propagation of upstream errors.
+//!
+//! A gateway or a proxy, having received a [Terror]
+//! from a service it calls, typically reports an error
+//! of its own, e.g. `502 Bad Gateway`, while keeping the
+//! original one in [Terror::upstream]. [Propagation]
+//! decides which upstream statuses are passed through
+//! as they are and which are remapped, and how deep the
+//! chain of upstream errors may grow.
+//!
+//! ### Examples
+//!
+//! ```rust
+//! use terror::upstream::Propagation;
+//! use terror::Terror;
+//!
+//! let policy = Propagation::new();
+//!
+//! let missing = Terror::new(404, "no such user").build();
+//! let reported = policy.propagate(missing).build();
+//! assert_eq!(404, reported.status);
+//!
+//! let failed = Terror::new(500, "database unavailable").build();
+//! let reported = policy.propagate(failed).build();
+//! assert_eq!(502, reported.status);
+//! assert_eq!(500, reported.upstream[0].status);
+//! ```
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
+use crate::{Builder, Terror};
+
+/// Default number of levels of upstream
+/// errors kept by [Propagation].
+pub const DEFAULT_DEPTH: usize = 3;
+
+/// Status reported for upstream errors,
+/// which are not passed through.
+const BAD_GATEWAY: u16 = 502;
+
+/// Status of upstream timeouts.
+const GATEWAY_TIMEOUT: u16 = 504;
+
+/// Message reported for upstream errors,
+/// which are not passed through.
+const UPSTREAM_MESSAGE: &str = "upstream service failed";
+
+/// A policy of upstream error propagation.
+///
+/// Statuses are decided as follows:
+/// 1. statuses [remapped](Propagation::remap)
+///    explicitly are reported as configured;
+/// 2. statuses [passed through](Propagation::pass_through)
+///    are reported as they are;
+/// 3. the rest are reported with the
+///    [fallback](Propagation::fallback) status.
+///
+/// By default, client errors (`4xx`) and
+/// `504 Gateway Timeout` are passed through, while
+/// the rest are reported as `502 Bad Gateway`, and
+/// up to [DEFAULT_DEPTH] levels of upstream errors
+/// are kept.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Propagation {
+    pass_through: Vec<RangeInclusive<u16>>,
+    remapped: HashMap<u16, u16>,
+    fallback: u16,
+    depth: usize
+}
+
+impl Propagation {
+
+    /// Constructs the default policy.
+    pub fn new() -> Propagation {
+        Propagation::opaque()
+            .pass_through_range(400..=499)
+            .pass_through(GATEWAY_TIMEOUT)
+    }
+
+    /// Constructs a policy, which passes
+    /// nothing through.
+    pub fn opaque() -> Propagation {
+        Propagation {
+            pass_through: Vec::new(),
+            remapped: HashMap::new(),
+            fallback: BAD_GATEWAY,
+            depth: DEFAULT_DEPTH
+        }
+    }
+
+    /// Passes the upstream status through.
+    pub fn pass_through(self, status: u16) -> Propagation {
+        self.pass_through_range(status..=status)
+    }
+
+    /// Passes the range of upstream statuses through.
+    pub fn pass_through_range(mut self, statuses: RangeInclusive<u16>) -> Propagation {
+        self.pass_through.push(statuses);
+        self
+    }
+
+    /// Reports the upstream status `from` as `to`.
+    pub fn remap(mut self, from: u16, to: u16) -> Propagation {
+        self.remapped.insert(from, to);
+        self
+    }
+
+    /// Sets the status reported for upstream
+    /// statuses, which are neither passed
+    /// through nor remapped explicitly.
+    pub fn fallback(mut self, status: u16) -> Propagation {
+        self.fallback = status;
+        self
+    }
+
+    /// Sets the number of levels of upstream errors
+    /// nested into the reported one; `0` keeps none.
+    pub fn depth(mut self, depth: usize) -> Propagation {
+        self.depth = depth;
+        self
+    }
+
+    /// Tells whether the upstream status is
+    /// reported as it is.
+    pub fn passes_through(&self, status: u16) -> bool {
+        !self.remapped.contains_key(&status)
+            && self.pass_through.iter().any(|range| range.contains(&status))
+    }
+
+    /// Decides the status to report
+    /// for the upstream one.
+    pub fn status_for(&self, status: u16) -> u16 {
+        match self.remapped.get(&status) {
+            Some(remapped) => *remapped,
+            None if self.passes_through(status) => status,
+            None => self.fallback
+        }
+    }
+
+    /// Constructs a builder of the error to report for
+    /// the upstream one.
+    ///
+    /// If the upstream status is passed through, so is
+    /// the upstream error itself: its message, short
+    /// message, error code, details, violations, item
+    /// errors, kept template, reference and source are
+    /// reported, and its own upstream errors are nested. Otherwise, a generic
+    /// message is reported and the upstream error is
+    /// nested.
+    pub fn propagate(&self, upstream: Terror) -> Builder {
+        let (builder, mut nested) = match self.passes_through(upstream.status) {
+            true => {
+                let mut builder = Terror::new(upstream.status, upstream.message)
+                    .details(upstream.details)
+                    .add_violations(upstream.violations)
+                    .add_item_errors(upstream.errors);
+                if let Some(shorthand) = upstream.short_message {
+                    builder = builder.shorthand(shorthand);
+                }
+                if let Some(code) = upstream.error_code {
+                    builder = builder.error_code(code);
+                }
+                #[cfg(feature = "mdn")]
+                { builder = builder.reference(upstream.reference); }
+                builder.template = upstream.template;
+                builder.source = upstream.source;
+                (builder, upstream.upstream)
+            },
+            false => (Terror::new(self.status_for(upstream.status), UPSTREAM_MESSAGE), vec![upstream])
+        };
+
+        match self.depth {
+            0 => builder,
+            depth => {
+                nested.iter_mut()
+                    .for_each(|upstream| upstream.truncate_upstream(depth - 1));
+                builder.add_upstreams(nested)
+            }
+        }
+    }
+
+}
+
+impl Default for Propagation {
+    fn default() -> Self {
+        Propagation::new()
+    }
+}
+
+#[cfg(test)]
+mod upstream_test {
+    use serde_json::json;
+    use crate::upstream::Propagation;
+    use crate::{MissingKey, Terror};
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn default_policy() {
+        let policy = Propagation::default();
+
+        assert_eq!(404, policy.status_for(404));
+        assert_eq!(429, policy.status_for(429));
+        assert_eq!(504, policy.status_for(504));
+        assert_eq!(502, policy.status_for(500));
+        assert_eq!(502, policy.status_for(503));
+    }
+
+    #[test]
+    fn custom_policy() {
+        let policy = Propagation::opaque()
+            .pass_through(409)
+            .pass_through_range(500..=599)
+            .remap(503, 504)
+            .fallback(500);
+
+        assert!(policy.passes_through(409));
+        assert!(!policy.passes_through(503));
+        assert_eq!(409, policy.status_for(409));
+        assert_eq!(500, policy.status_for(404));
+        assert_eq!(501, policy.status_for(501));
+        assert_eq!(504, policy.status_for(503));
+    }
+
+    #[test]
+    fn propagate_passed_through() {
+        let root = Terror::new(404, "no such account").build();
+        let upstream = Terror::new(404, "no such user")
+            .error_code("user.missing")
+            .add_text_detail("user", "1")
+            .add_upstream(root.clone())
+            .build();
+
+        let reported = Propagation::new()
+            .propagate(upstream.clone())
+            .build();
+
+        assert_eq!(404, reported.status);
+        assert_eq!("no such user", reported.message);
+        assert_eq!(Some(String::from("user.missing")), reported.error_code);
+        assert_eq!(upstream.details, reported.details);
+        assert_eq!(vec![root], reported.upstream);
+    }
+
+    #[test]
+    fn propagate_passed_through_w_template() {
+        let builder = Terror::new(404, "user {user_id} not found")
+            .add_int_detail("user_id", 42)
+            .interpolate(MissingKey::Keep)
+            .keep_template();
+        #[cfg(feature = "mdn")]
+        let builder = builder.reference("https://docs.example.com/errors/user.missing");
+        let upstream = builder.build();
+
+        let reported = Propagation::new()
+            .propagate(upstream.clone())
+            .build();
+
+        assert_eq!("user 42 not found", reported.message);
+        assert!(reported.template.is_some());
+        assert_eq!(upstream.template, reported.template);
+        #[cfg(feature = "mdn")]
+        assert_eq!("https://docs.example.com/errors/user.missing", reported.reference);
+    }
+
+    #[test]
+    fn propagate_remapped() {
+        let upstream = Terror::new(500, "database unavailable")
+            .error_code("db.down")
+            .build();
+
+        let reported = Propagation::new()
+            .propagate(upstream.clone())
+            .build();
+
+        assert_eq!(502, reported.status);
+        assert_eq!("upstream service failed", reported.message);
+        assert_eq!(None, reported.error_code);
+        assert_eq!(vec![upstream], reported.upstream);
+    }
+
+    #[test]
+    fn propagate_passed_through_w_depth() {
+        let root = Terror::new(503, "database unavailable").build();
+        let middle = Terror::new(502, "accounts unavailable")
+            .add_upstream(root)
+            .build();
+        let upstream = Terror::new(504, "users timed out")
+            .add_upstream(middle)
+            .build();
+
+        let kept = Propagation::new()
+            .depth(2)
+            .propagate(upstream.clone())
+            .build();
+        assert_eq!(1, kept.upstream[0].upstream.len());
+
+        let truncated = Propagation::new()
+            .depth(1)
+            .propagate(upstream.clone())
+            .build();
+        assert_eq!(1, truncated.upstream.len());
+        assert!(truncated.upstream[0].upstream.is_empty());
+
+        let dropped = Propagation::new()
+            .depth(0)
+            .propagate(upstream)
+            .build();
+        assert!(dropped.upstream.is_empty());
+    }
+
+    #[test]
+    fn propagate_w_depth() {
+        let root = Terror::new(503, "database unavailable").build();
+        let middle = Terror::new(502, "users unavailable")
+            .add_upstream(root)
+            .build();
+
+        let kept = Propagation::new()
+            .propagate(middle.clone())
+            .build();
+        assert_eq!(1, kept.upstream[0].upstream.len());
+
+        let truncated = Propagation::new()
+            .depth(1)
+            .propagate(middle.clone())
+            .build();
+        assert!(truncated.upstream[0].upstream.is_empty());
+
+        let dropped = Propagation::new()
+            .depth(0)
+            .propagate(middle)
+            .build();
+        assert!(dropped.upstream.is_empty());
+    }
+
+    #[test]
+    fn serialize_upstream() -> R {
+        let upstream = Terror::new(500, "database unavailable")
+            .error_code("db.down")
+            .build();
+        let reported = Propagation::new()
+            .propagate(upstream)
+            .build();
+
+        let actual = serde_json::to_value(&reported)?;
+        assert_eq!(json!(500), actual["upstream"][0]["status"]);
+        assert_eq!(json!("db.down"), actual["upstream"][0]["error_code"]);
+
+        let as_struct: Terror = serde_json::from_value(actual)?;
+        assert_eq!(reported, as_struct);
+        Ok(())
+    }
+
+    #[test]
+    fn upstream_as_error_source() {
+        use std::error::Error;
+
+        let reported = Propagation::new()
+            .propagate(Terror::new(500, "database unavailable").build())
+            .build();
+
+        let source = reported.source().unwrap();
+        assert_eq!("(500) :: database unavailable", source.to_string());
+    }
+
+}
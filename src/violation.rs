@@ -0,0 +1,261 @@
+use serde_derive::{Serialize, Deserialize};
+use serde_json::Value;
+
+#[cfg(feature = "validator")]
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+#[cfg(feature = "validator")]
+use crate::Terror;
+
+/// A single field-level validation failure,
+/// typically reported along with `400 Bad Request`
+/// or `422 Unprocessable Entity`.
+///
+/// ### Examples
+///
+/// ```rust
+/// use serde_json::json;
+/// use terror::{Terror, Violation};
+/// let built = Terror::new(422, "invalid user")
+///     .add_violation(
+///         Violation::new("address.zip", "must be 5 digits long")
+///             .code("length")
+///             .rejected_value(json!("123"))
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "utoipa", schema(description = "Field-level validation failure"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(description = "Field-level validation failure"))]
+pub struct Violation {
+
+    /// Path to the offending field
+    pub field: String,
+
+    /// Description of the failure
+    pub message: String,
+
+    /// Failure code; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+
+    /// The value, which was rejected; nullable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "utoipa", schema(value_type = Option<Value>))]
+    pub rejected_value: Option<Value>
+
+}
+
+impl Violation {
+
+    /// Constructs a new violation of the
+    /// provided field.
+    pub fn new<F, M>(field: F, message: M) -> Violation
+        where F: Into<String>,
+              M: Into<String>
+    {
+        Violation {
+            field: field.into(),
+            message: message.into(),
+            code: None,
+            rejected_value: None,
+        }
+    }
+
+    /// Adds a failure code.
+    pub fn code<K: Into<String>>(mut self, code: K) -> Violation {
+        let into: String = code.into();
+        self.code = Some(into);
+        self
+    }
+
+    /// Adds the rejected value.
+    pub fn rejected_value(mut self, value: Value) -> Violation {
+        self.rejected_value = Some(value);
+        self
+    }
+
+}
+
+/// Message reported for [ValidationErrors].
+#[cfg(feature = "validator")]
+const VALIDATION_MESSAGE: &str = "validation failed";
+
+/// Name `validator` uses for struct-level errors.
+#[cfg(feature = "validator")]
+const STRUCT_LEVEL_FIELD: &str = "__all__";
+
+#[cfg(feature = "validator")]
+impl From<ValidationErrors> for Terror {
+
+    /// Reports `422 Unprocessable Entity` with a
+    /// [Violation] per each validation error.
+    ///
+    /// Fields of nested structs and lists are
+    /// addressed as `address.zip` and `items[0].name`
+    /// respectively; struct-level errors are reported
+    /// against the path of the struct itself. The
+    /// rejected value is taken from the `value`
+    /// parameter, if `validator` reports one.
+    fn from(errors: ValidationErrors) -> Self {
+        let mut violations = Vec::new();
+        collect_violations("", &errors, &mut violations);
+        violations.sort_by(|a, b| a.field.cmp(&b.field)
+            .then_with(|| a.code.cmp(&b.code)));
+
+        Terror::new(422, VALIDATION_MESSAGE)
+            .add_violations(violations)
+            .build()
+    }
+
+}
+
+/// Flattens nested validation errors.
+#[cfg(feature = "validator")]
+fn collect_violations(prefix: &str,
+                      errors: &ValidationErrors,
+                      into: &mut Vec<Violation>) {
+    for (field, kind) in errors.errors() {
+        let path = match (prefix.is_empty(), field.as_ref()) {
+            (_, STRUCT_LEVEL_FIELD) => String::from(prefix),
+            (true, field) => String::from(field),
+            (false, field) => format!("{}.{}", prefix, field)
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                for error in errors {
+                    let message = error.message.as_ref()
+                        .unwrap_or(&error.code)
+                        .to_string();
+                    let mut violation = Violation::new(path.clone(), message)
+                        .code(error.code.to_string());
+                    violation.rejected_value = error.params.get("value").cloned();
+                    into.push(violation);
+                }
+            },
+            ValidationErrorsKind::Struct(errors) => collect_violations(&path, errors, into),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_violations(&format!("{}[{}]", path, index), errors, into);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod violation_test {
+    use serde_json::json;
+    use crate::{Terror, Violation};
+
+    type R = anyhow::Result<()>;
+
+    #[test]
+    fn serialize_violations() -> R {
+        let built = Terror::new(422, "invalid user")
+            .add_violation(Violation::new("name", "must not be blank"))
+            .add_violation(
+                Violation::new("address.zip", "must be 5 digits long")
+                    .code("length")
+                    .rejected_value(json!("123"))
+            )
+            .build();
+
+        let actual = serde_json::to_value(&built)?;
+        assert_eq!(
+            json!([
+                {
+                    "field": "name",
+                    "message": "must not be blank"
+                },
+                {
+                    "field": "address.zip",
+                    "message": "must be 5 digits long",
+                    "code": "length",
+                    "rejected_value": "123"
+                }
+            ]),
+            actual["violations"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn skip_empty_violations() -> R {
+        let built = Terror::new(422, "invalid user")
+            .build();
+
+        let actual = serde_json::to_value(&built)?;
+        assert!(actual.get("violations").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn deserialize_violations() -> R {
+        let inbound = json!({
+            "status": 400,
+            "message": "invalid user",
+            "violations": [
+                {
+                    "field": "age",
+                    "message": "must be positive",
+                    "rejected_value": -1
+                }
+            ]
+        });
+
+        let as_struct: Terror = serde_json::from_value(inbound)?;
+        assert_eq!(
+            vec![Violation::new("age", "must be positive").rejected_value(json!(-1))],
+            as_struct.violations
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "validator")]
+    fn from_validation_errors() {
+        use std::borrow::Cow;
+        use std::collections::BTreeMap;
+        use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+        let mut zip = ValidationError::new("length")
+            .with_message(Cow::from("must be 5 digits long"));
+        zip.add_param(Cow::from("value"), &"123");
+        let mut address = ValidationErrors::new();
+        address.add("zip", zip);
+
+        let mut item = ValidationErrors::new();
+        item.add("name", ValidationError::new("required"));
+
+        let mut errors = ValidationErrors::new();
+        errors.add("__all__", ValidationError::new("passwords_mismatch"));
+        errors.errors_mut().insert(
+            Cow::from("address"),
+            ValidationErrorsKind::Struct(Box::new(address))
+        );
+        errors.errors_mut().insert(
+            Cow::from("items"),
+            ValidationErrorsKind::List(BTreeMap::from([(1, Box::new(item))]))
+        );
+
+        let built: Terror = errors.into();
+
+        assert_eq!(422, built.status);
+        assert_eq!(
+            vec![
+                Violation::new("", "passwords_mismatch")
+                    .code("passwords_mismatch"),
+                Violation::new("address.zip", "must be 5 digits long")
+                    .code("length")
+                    .rejected_value(json!("123")),
+                Violation::new("items[1].name", "required")
+                    .code("required"),
+            ],
+            built.violations
+        );
+    }
+
+}
@@ -0,0 +1,111 @@
+use actix_web::body::BoxBody;
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, Responder, ResponseError};
+
+use std::fmt::Debug;
+
+use crate::{Details, Terror};
+
+impl<D: Details + Debug> ResponseError for Terror<D> {
+
+    /// Takes the HTTP status from [Terror::status].
+    /// Statuses outside of the valid range are reported
+    /// as `500 Internal Server Error`.
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Produces a JSON response with the
+    /// serialised [Terror] as a body.
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        match serde_json::to_vec(self) {
+            Ok(body) => HttpResponse::build(self.status_code())
+                .insert_header((CONTENT_TYPE, super::JSON_CONTENT_TYPE))
+                .body(body),
+            Err(err) => HttpResponse::InternalServerError()
+                .insert_header((CONTENT_TYPE, super::TEXT_CONTENT_TYPE))
+                .body(err.to_string())
+        }
+    }
+
+}
+
+impl<D: Details + Debug> Responder for Terror<D> {
+    type Body = BoxBody;
+
+    /// Responds the same way as [ResponseError::error_response].
+    fn respond_to(self, _req: &HttpRequest) -> HttpResponse<Self::Body> {
+        self.error_response()
+    }
+
+}
+
+#[cfg(test)]
+mod actix_test {
+    use actix_web::body;
+    use actix_web::http::header::CONTENT_TYPE;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use actix_web::{Responder, ResponseError};
+    use serde_json::Value;
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[tokio::test]
+    async fn error_response() -> R {
+        let terror = Terror::new(404, "no such user")
+            .error_code("user.missing")
+            .build();
+
+        assert_eq!(StatusCode::NOT_FOUND, terror.status_code());
+
+        let response = terror.error_response();
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_eq!(
+            "application/json",
+            response.headers().get(CONTENT_TYPE).unwrap()
+        );
+
+        let bytes = body::to_bytes(response.into_body()).await.unwrap();
+        let actual: Value = serde_json::from_slice(&bytes)?;
+        assert_eq!(serde_json::to_value(terror)?, actual);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_response_w_invalid_status() -> R {
+        let terror = Terror::new(1000, "no such status")
+            .build();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, terror.status_code());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn respond_to() -> R {
+        let request = TestRequest::default().to_http_request();
+        let response = Terror::new(409, "version conflict")
+            .build()
+            .respond_to(&request);
+
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn into_actix_error() -> R {
+        let error: actix_web::Error = Terror::new(429, "slow down")
+            .build()
+            .into();
+
+        assert_eq!(
+            StatusCode::TOO_MANY_REQUESTS,
+            error.as_response_error().status_code()
+        );
+        Ok(())
+    }
+
+}
@@ -0,0 +1,82 @@
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+use crate::{Details, Terror};
+
+impl<D: Details> IntoResponse for Terror<D> {
+
+    /// Produces a JSON response with the HTTP
+    /// status taken from [Terror::status]. Statuses
+    /// outside of the valid range are reported as
+    /// `500 Internal Server Error`.
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        match serde_json::to_vec(&self) {
+            Ok(body) => (
+                status,
+                [(header::CONTENT_TYPE, HeaderValue::from_static(super::JSON_CONTENT_TYPE))],
+                body
+            ).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                [(header::CONTENT_TYPE, HeaderValue::from_static(super::TEXT_CONTENT_TYPE))],
+                err.to_string()
+            ).into_response()
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod axum_test {
+    use axum::body;
+    use axum::http::{header, StatusCode};
+    use axum::response::IntoResponse;
+    use serde_json::Value;
+    use crate::Terror;
+
+    type R = anyhow::Result<()>;
+
+    #[tokio::test]
+    async fn into_response() -> R {
+        let terror = Terror::new(404, "no such user")
+            .error_code("user.missing")
+            .build();
+
+        let response = terror.clone().into_response();
+
+        assert_eq!(StatusCode::NOT_FOUND, response.status());
+        assert_eq!(
+            "application/json",
+            response.headers().get(header::CONTENT_TYPE).unwrap()
+        );
+
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await?;
+        let actual: Value = serde_json::from_slice(&bytes)?;
+        assert_eq!(serde_json::to_value(terror)?, actual);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn into_response_w_invalid_status() -> R {
+        let response = Terror::new(1000, "no such status")
+            .build()
+            .into_response();
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, response.status());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn handler_result() -> R {
+        async fn handler() -> Result<&'static str, Terror> {
+            Err(Terror::new(409, "version conflict").build())
+        }
+
+        let response = handler().await.into_response();
+        assert_eq!(StatusCode::CONFLICT, response.status());
+        Ok(())
+    }
+
+}
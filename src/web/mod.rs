@@ -0,0 +1,15 @@
+//! Integrations with web frameworks, which
+//! allow returning [Terror](crate::Terror)
+//! from request handlers directly.
+
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "actix")]
+mod actix;
+
+/// Content type of a serialised [Terror](crate::Terror).
+const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Content type of a fallback error response, sent
+/// if [Terror](crate::Terror) fails to serialise.
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
@@ -0,0 +1,262 @@
+//! Derive macro for `terror`; see [IntoTerror].
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt, LitStr, Result};
+
+/// Name of the helper attribute.
+const ATTR: &str = "terror";
+
+/// Name the converted value is bound to; chosen
+/// so that it cannot collide with field bindings.
+const SELF: &str = "__terror_self";
+
+/// Status assumed if none is configured, same
+/// as in `Terror::from_error`.
+const DEFAULT_STATUS: u16 = 500;
+
+/// Derives `From<T> for terror::Terror`.
+///
+/// ### Attributes
+///
+/// The conversion is configured with `#[terror(...)]`
+/// attributes. On enum variants (or on the struct itself):
+/// * `status = 404` - HTTP status; defaults to the one set
+///   on the enum, or `500` if there is none;
+/// * `code = "..."` - error code;
+/// * `shorthand = "..."` - short error message;
+/// * `message = "..."` - error message; a format string,
+///   which may refer to the fields by name, or by index
+///   for tuple variants. If omitted, the [Display] output
+///   of the value is used.
+///
+/// On fields:
+/// * `detail` - adds the field as a detail named after
+///   the field itself;
+/// * `detail = "..."` - adds the field as a detail with
+///   the provided name; required for tuple fields.
+///
+/// Fields become details via `Builder::add_struct_detail_lenient`,
+/// so they must implement `Serialize`; the conversion never
+/// panics, as fields, which fail to serialise, are added as
+/// placeholders.
+///
+/// ### Examples
+///
+/// ```rust,ignore
+/// use terror::IntoTerror;
+///
+/// #[derive(Debug, IntoTerror)]
+/// #[terror(status = 500)]
+/// enum UserError {
+///     #[terror(status = 404, code = "user.missing", message = "user {id} not found")]
+///     Missing { #[terror(detail)] id: u64 },
+///     #[terror(status = 409, code = "user.conflict", message = "version {0} is stale")]
+///     Conflict(#[terror(detail = "version")] u32),
+/// }
+/// ```
+///
+/// [Display]: std::fmt::Display
+#[proc_macro_derive(IntoTerror, attributes(terror))]
+pub fn derive_into_terror(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Settings of a single enum variant or a struct.
+#[derive(Default)]
+struct Mapping {
+    status: Option<u16>,
+    code: Option<LitStr>,
+    shorthand: Option<LitStr>,
+    message: Option<LitStr>,
+}
+
+impl Mapping {
+
+    /// Parses all `#[terror(...)]` attributes.
+    fn parse(attrs: &[Attribute]) -> Result<Mapping> {
+        let mut mapping = Mapping::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident(ATTR)) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("status") {
+                    let status: LitInt = meta.value()?.parse()?;
+                    mapping.status = Some(status.base10_parse()?);
+                } else if meta.path.is_ident("code") {
+                    mapping.code = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("shorthand") {
+                    mapping.shorthand = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("message") {
+                    mapping.message = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("expected `status`, `code`, `shorthand` or `message`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(mapping)
+    }
+
+}
+
+/// A field bound in a match arm.
+struct Binding {
+    ident: Ident,
+    detail: Option<LitStr>,
+}
+
+impl Binding {
+
+    /// Parses the field attributes; `name` is
+    /// the field name, if the field is named.
+    fn parse(ident: Ident, name: Option<&Ident>, attrs: &[Attribute]) -> Result<Binding> {
+        let mut detail = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident(ATTR)) {
+            attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("detail") {
+                    return Err(meta.error("expected `detail`"));
+                }
+                if meta.input.peek(syn::Token![=]) {
+                    detail = Some(meta.value()?.parse()?);
+                } else if let Some(name) = name {
+                    detail = Some(LitStr::new(&name.to_string(), name.span()));
+                } else {
+                    return Err(meta.error("tuple fields require a name: `detail = \"...\"`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(Binding { ident, detail })
+    }
+
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let ty = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let defaults = Mapping::parse(&input.attrs)?;
+
+    let arms = match &input.data {
+        Data::Enum(data) => data.variants.iter()
+            .map(|variant| {
+                let mapping = Mapping::parse(&variant.attrs)?;
+                let ident = &variant.ident;
+                arm(quote!(#ty::#ident), &variant.fields, &mapping, &defaults)
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Data::Struct(data) => vec![
+            arm(quote!(#ty), &data.fields, &Mapping::default(), &defaults)?
+        ],
+        Data::Union(_) => return Err(Error::new(
+            Span::call_site(),
+            "IntoTerror cannot be derived for unions"
+        ))
+    };
+
+    let this = format_ident!("{}", SELF);
+    let scrutinee = match arms.is_empty() {
+        true => quote!(#this),
+        false => quote!(&#this)
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#ty #ty_generics> for ::terror::Terror #where_clause {
+            fn from(#this: #ty #ty_generics) -> Self {
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Generates a match arm, which destructures
+/// the value and builds the [Terror].
+fn arm(path: TokenStream2,
+       fields: &Fields,
+       mapping: &Mapping,
+       defaults: &Mapping) -> Result<TokenStream2> {
+    let bindings = fields.iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(name) => Binding::parse(name.clone(), Some(name), &field.attrs),
+            None => Binding::parse(format_ident!("_{}", index), None, &field.attrs)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let idents = bindings.iter().map(|binding| &binding.ident);
+    let pattern = match fields {
+        Fields::Named(_) => quote!(#path { #(#idents),* }),
+        Fields::Unnamed(_) => quote!(#path ( #(#idents),* )),
+        Fields::Unit => quote!(#path)
+    };
+
+    let status = mapping.status
+        .or(defaults.status)
+        .unwrap_or(DEFAULT_STATUS);
+    let message = match mapping.message.as_ref().or(defaults.message.as_ref()) {
+        Some(message) => {
+            let message = match fields {
+                Fields::Unnamed(_) => LitStr::new(&positional_to_named(&message.value()), message.span()),
+                _ => message.clone()
+            };
+            quote!(::std::format!(#message))
+        },
+        None => {
+            let this = format_ident!("{}", SELF);
+            quote!(::std::string::ToString::to_string(&#this))
+        }
+    };
+    let code = mapping.code.as_ref()
+        .or(defaults.code.as_ref())
+        .map(|code| quote!(.error_code(#code)));
+    let shorthand = mapping.shorthand.as_ref()
+        .or(defaults.shorthand.as_ref())
+        .map(|shorthand| quote!(.shorthand(#shorthand)));
+    let details = bindings.iter()
+        .filter_map(|binding| binding.detail.as_ref().map(|name| (name, &binding.ident)))
+        .map(|(name, ident)| quote!(.add_struct_detail_lenient(#name, #ident)));
+
+    Ok(quote! {
+        #[allow(unused_variables)]
+        #pattern => ::terror::Terror::new(#status, #message)
+            #code
+            #shorthand
+            #(#details)*
+            .build(),
+    })
+}
+
+/// Rewrites positional placeholders like `{0}` into
+/// references to the tuple field bindings like `{_0}`.
+fn positional_to_named(message: &str) -> String {
+    let mut result = String::with_capacity(message.len());
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        result.push(c);
+        if c != '{' {
+            continue;
+        }
+        match chars.peek() {
+            Some('{') => result.push(chars.next().unwrap()),
+            Some(d) if d.is_ascii_digit() => result.push('_'),
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod derive_test {
+    use crate::positional_to_named;
+
+    #[test]
+    fn rewrite_positional_placeholders() {
+        assert_eq!("{_0} and {_1:?}", positional_to_named("{0} and {1:?}"));
+        assert_eq!("{{0}} and {name}", positional_to_named("{{0}} and {name}"));
+        assert_eq!("no placeholders", positional_to_named("no placeholders"));
+    }
+
+}
@@ -0,0 +1,28 @@
+#![cfg(feature = "time")]
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use terror::clock::{Frozen, Mock};
+use terror::Terror;
+
+#[test]
+fn process_wide_clock() {
+    let at = DateTime::parse_from_rfc3339("2022-01-01T21:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    terror::clock::set_clock(Frozen(at));
+    assert_eq!(at, Terror::new(404, "generic error").build().timestamp);
+
+    let mock = Arc::new(Mock::new(at));
+    terror::clock::set_clock(mock.clone());
+    mock.advance(Duration::from_secs(300));
+    assert_eq!(
+        at + chrono::Duration::minutes(5),
+        Terror::new(404, "generic error").build().timestamp
+    );
+
+    terror::clock::reset_clock();
+    assert_ne!(at, Terror::new(404, "generic error").build().timestamp);
+}
@@ -0,0 +1,38 @@
+use terror::catalog::Catalog;
+
+/// Code generated from `codegen/errors.toml`.
+mod generated {
+    include!("codegen/errors.rs");
+}
+
+use generated::{ApiError, ALL, USER_MISSING};
+
+#[test]
+fn generated_code_compiles() {
+    let catalog = Catalog::from_defs(ALL).unwrap();
+    assert_eq!(2, catalog.len());
+    assert_eq!(Some(&USER_MISSING), catalog.get("user.missing"));
+
+    let built = ApiError::UserMissing.builder()
+        .build();
+    assert_eq!(404, built.status);
+    assert_eq!(Some(String::from("Not found")), built.short_message);
+    assert_eq!(Some(String::from("user.missing")), built.error_code);
+    assert_eq!(409, ApiError::UserExists.def().status);
+
+    let built = ApiError::UserExists.builder()
+        .add_text_detail("name", "alice")
+        .build();
+    assert_eq!("user alice exists", built.message);
+}
+
+#[test]
+#[cfg(feature = "codegen")]
+fn generated_code_is_up_to_date() -> anyhow::Result<()> {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/codegen");
+    let defs = terror::codegen::Definitions::load(dir.join("errors.toml"))?;
+    let checked_in = std::fs::read_to_string(dir.join("errors.rs"))?;
+
+    assert_eq!(checked_in, defs.to_rust("ApiError"));
+    Ok(())
+}
@@ -0,0 +1,41 @@
+// Generated by terror::codegen; do not edit.
+
+/// no such user
+pub const USER_MISSING: ::terror::catalog::ErrorDef = ::terror::catalog::ErrorDef::new("user.missing", 404, "no such user")
+    .shorthand("Not found")
+    .doc_url("https://docs.example.com/errors/user.missing");
+
+/// user {name} exists
+pub const USER_EXISTS: ::terror::catalog::ErrorDef = ::terror::catalog::ErrorDef::new("user.exists", 409, "user {name} exists");
+
+/// All the errors, in order of definition.
+pub const ALL: [&::terror::catalog::ErrorDef; 2] = [
+    &USER_MISSING,
+    &USER_EXISTS,
+];
+
+/// Codes of the errors.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ApiError {
+    /// no such user
+    UserMissing,
+    /// user {name} exists
+    UserExists,
+}
+
+impl ApiError {
+
+    /// Looks up the definition of the error.
+    pub const fn def(self) -> &'static ::terror::catalog::ErrorDef {
+        match self {
+            ApiError::UserMissing => &USER_MISSING,
+            ApiError::UserExists => &USER_EXISTS,
+        }
+    }
+
+    /// Constructs a new builder of the error.
+    pub fn builder(self) -> ::terror::Builder {
+        ::terror::Terror::from_def(self.def())
+    }
+
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "derive")]
+
+use std::fmt;
+use std::fmt::Formatter;
+use serde_derive::Serialize;
+use serde_json::json;
+use terror::{IntoTerror, Terror};
+
+#[derive(Debug, Serialize)]
+struct Version {
+    major: u32,
+    minor: u32
+}
+
+#[derive(Debug, IntoTerror)]
+#[terror(status = 400, code = "user.generic")]
+enum UserError {
+
+    #[terror(status = 404, code = "user.missing", shorthand = "No user", message = "user {id} not found")]
+    Missing {
+        #[terror(detail)]
+        id: u64,
+        #[allow(dead_code)]
+        internal: String
+    },
+
+    #[terror(status = 409, message = "version {0} is older than {1:?}")]
+    Conflict(
+        #[terror(detail = "requested")] u32,
+        #[terror(detail = "current")] Version
+    ),
+
+    #[terror(message = "malformed input")]
+    Malformed,
+
+    Outdated {
+        #[terror(detail)]
+        value: u32
+    },
+
+    Unknown
+
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown user error")
+    }
+}
+
+#[derive(Debug, IntoTerror)]
+#[terror(status = 503, code = "upstream.down", message = "{service} is unavailable")]
+struct Unavailable {
+    #[terror(detail = "upstream")]
+    service: String
+}
+
+#[test]
+fn named_fields() {
+    let terror: Terror = UserError::Missing { id: 25, internal: String::from("secret") }
+        .into();
+
+    assert_eq!(404, terror.status);
+    assert_eq!("user 25 not found", terror.message);
+    assert_eq!(Some(String::from("No user")), terror.short_message);
+    assert_eq!(Some(String::from("user.missing")), terror.error_code);
+    assert_eq!(1, terror.details.len());
+    assert_eq!(Some(&json!(25)), terror.details.get("id"));
+}
+
+#[test]
+fn tuple_fields() {
+    let terror: Terror = UserError::Conflict(3, Version { major: 1, minor: 2 })
+        .into();
+
+    assert_eq!(409, terror.status);
+    assert_eq!("version 3 is older than Version { major: 1, minor: 2 }", terror.message);
+    assert_eq!(Some(String::from("user.generic")), terror.error_code);
+    assert_eq!(Some(&json!(3)), terror.details.get("requested"));
+    assert_eq!(Some(&json!({ "major": 1, "minor": 2 })), terror.details.get("current"));
+}
+
+#[test]
+fn container_defaults() {
+    let terror: Terror = UserError::Malformed.into();
+
+    assert_eq!(400, terror.status);
+    assert_eq!("malformed input", terror.message);
+    assert_eq!(Some(String::from("user.generic")), terror.error_code);
+    assert!(terror.details.is_empty());
+}
+
+#[test]
+fn display_message() {
+    let terror: Terror = UserError::Unknown.into();
+
+    assert_eq!(400, terror.status);
+    assert_eq!("unknown user error", terror.message);
+}
+
+#[test]
+fn field_named_value() {
+    let terror: Terror = UserError::Outdated { value: 7 }.into();
+
+    assert_eq!("unknown user error", terror.message);
+    assert_eq!(Some(&json!(7)), terror.details.get("value"));
+}
+
+#[test]
+fn struct_mapping() {
+    let terror: Terror = Unavailable { service: String::from("billing") }
+        .into();
+
+    assert_eq!(503, terror.status);
+    assert_eq!("billing is unavailable", terror.message);
+    assert_eq!(Some(String::from("upstream.down")), terror.error_code);
+    assert_eq!(Some(&json!("billing")), terror.details.get("upstream"));
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn question_mark() {
+    fn handler() -> Result<(), Terror> {
+        Err(UserError::Malformed)?
+    }
+
+    assert_eq!(400, handler().unwrap_err().status);
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "err_id")]
+
+use terror::id::{Sequential, V7};
+use terror::Terror;
+
+#[test]
+fn process_wide_generator() {
+    terror::id::set_generator(Sequential::starting_at(100));
+    let first = Terror::new(404, "generic error").build();
+    let second = Terror::new(404, "generic error").build();
+
+    assert_eq!(100, first.id.as_u128());
+    assert_eq!(101, second.id.as_u128());
+
+    terror::id::set_generator(V7);
+    assert_eq!(7, Terror::new(404, "generic error").build().id.get_version_num());
+
+    terror::id::reset_generator();
+    assert_eq!(4, Terror::new(404, "generic error").build().id.get_version_num());
+}
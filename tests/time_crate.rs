@@ -0,0 +1,47 @@
+#![cfg(all(feature = "time_crate", not(feature = "time")))]
+
+use serde_json::json;
+use terror::clock::Frozen;
+use terror::timestamp::{Precision, TimestampFormat};
+use terror::Terror;
+use time::macros::datetime;
+
+#[test]
+fn same_wire_format_as_chrono() -> anyhow::Result<()> {
+    let at = datetime!(2022-01-01 21:00:00.25 UTC);
+    let built = Terror::new(404, "generic error")
+        .clock(&Frozen(at))
+        .build();
+
+    let cases = [
+        (TimestampFormat::Rfc3339, json!("2022-01-01T21:00:00.250Z")),
+        (TimestampFormat::Rfc3339Fixed(Precision::Micros), json!("2022-01-01T21:00:00.250000Z")),
+        (TimestampFormat::EpochMillis, json!(1641070800250i64)),
+    ];
+    for (format, expected) in cases {
+        terror::timestamp::set_format(format);
+
+        let actual = serde_json::to_value(&built)?;
+        assert_eq!(expected, actual["timestamp"]);
+
+        let as_struct: Terror = serde_json::from_value(actual)?;
+        assert_eq!(at, as_struct.timestamp);
+    }
+
+    terror::timestamp::set_format(TimestampFormat::default());
+    Ok(())
+}
+
+#[test]
+fn deserialize_offset_to_utc() -> anyhow::Result<()> {
+    let inbound = json!({
+        "status": 404,
+        "message": "generic error",
+        "timestamp": "2022-01-02T00:00:00+03:00"
+    });
+
+    let as_struct: Terror = serde_json::from_value(inbound)?;
+    assert_eq!(datetime!(2022-01-01 21:00:00 UTC), as_struct.timestamp);
+    assert!(as_struct.timestamp.offset().is_utc());
+    Ok(())
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "time")]
+
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use terror::timestamp::{Precision, TimestampFormat};
+use terror::{Problem, Terror};
+
+#[test]
+fn process_wide_format() -> anyhow::Result<()> {
+    let at = DateTime::parse_from_rfc3339("2022-01-01T21:00:00.25Z")?
+        .with_timezone(&Utc);
+    let at_seconds = DateTime::parse_from_rfc3339("2022-01-01T21:00:00Z")?
+        .with_timezone(&Utc);
+    let built = Terror::new(404, "generic error")
+        .timestamp(at)
+        .build();
+
+    let cases = [
+        (TimestampFormat::Rfc3339, json!("2022-01-01T21:00:00.250Z"), at),
+        (TimestampFormat::Rfc3339Fixed(Precision::Seconds), json!("2022-01-01T21:00:00Z"), at_seconds),
+        (TimestampFormat::Rfc3339Fixed(Precision::Micros), json!("2022-01-01T21:00:00.250000Z"), at),
+        (TimestampFormat::EpochSeconds, json!(1641070800), at_seconds),
+        (TimestampFormat::EpochMillis, json!(1641070800250i64), at),
+    ];
+    for (format, expected, restored) in cases {
+        terror::timestamp::set_format(format);
+        assert_eq!(format, terror::timestamp::format());
+
+        let actual = serde_json::to_value(&built)?;
+        assert_eq!(expected, actual["timestamp"]);
+
+        let problem: Problem = built.clone().into();
+        assert_eq!(Some(&expected), problem.extensions.get("timestamp"));
+
+        let as_struct: Terror = serde_json::from_value(actual)?;
+        assert_eq!(restored, as_struct.timestamp);
+
+        #[cfg(feature = "utoipa")]
+        {
+            let schema = serde_json::to_value(<Terror as utoipa::PartialSchema>::schema())?;
+            let schema_type = if expected.is_string() { "string" } else { "integer" };
+            assert_eq!(json!(schema_type), schema["properties"]["timestamp"]["type"]);
+        }
+    }
+
+    // epoch milliseconds before 1973 look like seconds
+    let early = DateTime::parse_from_rfc3339("1971-01-01T00:00:00Z")?
+        .with_timezone(&Utc);
+    let built = Terror::new(404, "generic error")
+        .timestamp(early)
+        .build();
+    for format in [TimestampFormat::EpochSeconds, TimestampFormat::EpochMillis] {
+        terror::timestamp::set_format(format);
+
+        let as_struct: Terror = serde_json::from_value(serde_json::to_value(&built)?)?;
+        assert_eq!(early, as_struct.timestamp);
+    }
+
+    terror::timestamp::set_format(TimestampFormat::default());
+    Ok(())
+}